@@ -0,0 +1,140 @@
+//! A minimal encoder for the Basic Encoding Rules of ASN.1 (ITU-T X.690), sufficient for the
+//! values of LDAP controls.
+
+
+const CLASS_CONTEXT: u8 = 0b1000_0000;
+const CONSTRUCTED: u8 = 0b0010_0000;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_SEQUENCE: u8 = 0x10;
+
+
+/// A value that can be encoded using BER.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Ber {
+    Integer(i64),
+    OctetString(Vec<u8>),
+    Sequence(Vec<Ber>),
+
+    /// A primitive value with a context-specific tag (`[n] IMPLICIT`), given as its encoded
+    /// contents.
+    ContextPrimitive(u8, Vec<u8>),
+
+    /// A constructed value with a context-specific tag (`[n] IMPLICIT SEQUENCE`).
+    ContextConstructed(u8, Vec<Ber>),
+}
+impl Ber {
+    /// A context-specific primitive BOOLEAN.
+    pub fn context_boolean(tag: u8, value: bool) -> Self {
+        Self::ContextPrimitive(tag, boolean_contents(value))
+    }
+
+    /// Encodes this value.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut ret = Vec::new();
+        self.encode_into(&mut ret);
+        ret
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Integer(i) => encode_element(buf, TAG_INTEGER, &integer_contents(*i)),
+            Self::OctetString(bs) => encode_element(buf, TAG_OCTET_STRING, bs),
+            Self::Sequence(elements) => encode_element(buf, CONSTRUCTED | TAG_SEQUENCE, &encode_all(elements)),
+            Self::ContextPrimitive(tag, contents) => encode_element(buf, CLASS_CONTEXT | tag, contents),
+            Self::ContextConstructed(tag, elements) => encode_element(buf, CLASS_CONTEXT | CONSTRUCTED | tag, &encode_all(elements)),
+        }
+    }
+}
+
+
+fn encode_all(elements: &[Ber]) -> Vec<u8> {
+    let mut contents = Vec::new();
+    for element in elements {
+        element.encode_into(&mut contents);
+    }
+    contents
+}
+
+
+/// Encodes an element with the given identifier octet (class, constructedness and tag number up
+/// to 30) and contents, using the definite length form.
+fn encode_element(buf: &mut Vec<u8>, identifier: u8, contents: &[u8]) {
+    buf.push(identifier);
+    if contents.len() < 0x80 {
+        // short form
+        buf.push(contents.len() as u8);
+    } else {
+        // long form: number of length bytes, then length bytes
+        let length_bytes = (contents.len() as u64).to_be_bytes();
+        let first_nonzero = length_bytes.iter().position(|b| *b != 0).unwrap();
+        buf.push(0x80 | (length_bytes.len() - first_nonzero) as u8);
+        buf.extend_from_slice(&length_bytes[first_nonzero..]);
+    }
+    buf.extend_from_slice(contents);
+}
+
+
+fn boolean_contents(value: bool) -> Vec<u8> {
+    vec![if value { 0xFF } else { 0x00 }]
+}
+
+
+/// Encodes an integer as two's complement in the minimal number of bytes.
+fn integer_contents(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        // a leading byte may only be dropped if the sign is preserved
+        let redundant =
+            (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Ber;
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(Ber::Integer(0).encode(), [0x02, 0x01, 0x00]);
+        assert_eq!(Ber::Integer(7).encode(), [0x02, 0x01, 0x07]);
+        assert_eq!(Ber::Integer(127).encode(), [0x02, 0x01, 0x7F]);
+        assert_eq!(Ber::Integer(128).encode(), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(Ber::Integer(256).encode(), [0x02, 0x02, 0x01, 0x00]);
+        assert_eq!(Ber::Integer(-1).encode(), [0x02, 0x01, 0xFF]);
+        assert_eq!(Ber::Integer(-128).encode(), [0x02, 0x01, 0x80]);
+        assert_eq!(Ber::Integer(-129).encode(), [0x02, 0x02, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn test_structures() {
+        assert_eq!(
+            Ber::Sequence(vec![Ber::Integer(7)]).encode(),
+            [0x30, 0x03, 0x02, 0x01, 0x07],
+        );
+        assert_eq!(
+            Ber::Sequence(vec![
+                Ber::OctetString(b"cn".to_vec()),
+                Ber::context_boolean(1, true),
+                Ber::ContextConstructed(0, vec![Ber::Integer(2)]),
+            ]).encode(),
+            [0x30, 0x0C, 0x04, 0x02, b'c', b'n', 0x81, 0x01, 0xFF, 0xA0, 0x03, 0x02, 0x01, 0x02],
+        );
+    }
+
+    #[test]
+    fn test_long_length() {
+        let encoded = Ber::OctetString(vec![0xAA; 300]).encode();
+        assert_eq!(encoded[0..4], [0x04, 0x82, 0x01, 0x2C]);
+        assert_eq!(encoded.len(), 304);
+    }
+}
@@ -0,0 +1,179 @@
+use ldap3::Ldap;
+
+use crate::error::Error;
+use crate::credentials::PasswordSource;
+use crate::opts::{BindMethod, ConnectionOpts, Credentials};
+
+
+/// The way in which to authenticate to the LDAP server.
+pub(crate) enum Authentication {
+    /// Simple bind with a DN and a password.
+    Simple { bind_dn: String, password: String },
+
+    /// SASL GSS-SPNEGO bind using NTLM with a `DOMAIN\user` login and a password.
+    Ntlm { login: String, password: String },
+
+    /// Anonymous simple bind (empty DN and password).
+    Anonymous,
+
+    /// SASL GSSAPI (Kerberos) bind with the ambient credentials.
+    ///
+    /// If no server FQDN is given, it is taken from the URL of the server.
+    SaslGssapi { server_fqdn: Option<String> },
+
+    /// SASL EXTERNAL bind, e.g. with a TLS client certificate.
+    SaslExternal,
+}
+impl Authentication {
+    /// Collects the information required for authentication from the command-line options.
+    ///
+    /// This may query the user for a password; it should therefore be called before the connection
+    /// to the server is established.
+    pub fn from_opts(o: &ConnectionOpts) -> Result<Self, Error> {
+        let bind_method = if o.anonymous { BindMethod::Anonymous } else { o.bind_method };
+        match bind_method {
+            BindMethod::Simple | BindMethod::Ntlm => {},
+            BindMethod::Anonymous => return Ok(Self::Anonymous),
+            BindMethod::Gssapi => return Ok(Self::SaslGssapi { server_fqdn: o.sasl_host.clone() }),
+            BindMethod::External => return Ok(Self::SaslExternal),
+        }
+
+        let cli_password_source = PasswordSource::from_opts(o);
+        let (bind_dn, password_source) = if let Some(credentials_file) = o.credentials_file.as_ref() {
+            let creds_file_string = std::fs::read_to_string(credentials_file)
+                .map_err(|error| Error::Io {
+                    context: format!("failed to read credentials file {}", credentials_file.display()),
+                    error,
+                })?;
+            let creds: Credentials = toml::from_str(&creds_file_string)
+                .map_err(|e| Error::Parse {
+                    context: format!("failed to parse credentials file {}", credentials_file.display()),
+                    message: e.to_string(),
+                })?;
+            let password_source = match cli_password_source {
+                Some(ps) => ps,
+                None => PasswordSource::from_credentials(&creds)
+                    .map_err(|message| Error::Parse {
+                        context: format!("invalid credentials file {}", credentials_file.display()),
+                        message,
+                    })?,
+            };
+            if let Some(bind_dn) = o.bind_dn.as_ref() {
+                (bind_dn.clone(), password_source)
+            } else {
+                (creds.bind_dn, password_source)
+            }
+        } else if let Some(bind_dn) = o.bind_dn.as_ref() {
+            (bind_dn.clone(), cli_password_source.unwrap_or(PasswordSource::Prompt))
+        } else {
+            return Err(Error::Usage(
+                "at least one of -D/--bind-dn or -c/--credentials-file must be given for a simple or NTLM bind; use --anonymous to bind anonymously".to_owned()
+            ));
+        };
+        if bind_method == BindMethod::Ntlm {
+            check_ntlm_login(&bind_dn)?;
+        }
+        let password = password_source.obtain(&bind_dn)?;
+        if bind_method == BindMethod::Ntlm {
+            Ok(Self::Ntlm { login: bind_dn, password })
+        } else {
+            Ok(Self::Simple { bind_dn, password })
+        }
+    }
+
+    /// Binds (logs in) to the LDAP server with the given URL.
+    pub async fn bind(&self, ldap: &mut Ldap, url: &str) -> Result<(), Error> {
+        match self {
+            Self::Simple { bind_dn, password } => {
+                ldap.simple_bind(bind_dn, password)
+                    .await.and_then(|r| r.success())
+                    .map_err(Error::ldap("bind"))?;
+            },
+            Self::Ntlm { login, password } => {
+                ldap.sasl_ntlm_bind(login, password)
+                    .await.and_then(|r| r.success())
+                    .map_err(Error::ldap("NTLM bind"))?;
+            },
+            Self::Anonymous => {
+                ldap.simple_bind("", "")
+                    .await.and_then(|r| r.success())
+                    .map_err(Error::ldap("anonymous bind"))?;
+            },
+            Self::SaslGssapi { server_fqdn } => {
+                let server_fqdn = match server_fqdn {
+                    Some(sf) => sf.clone(),
+                    None => host_from_url(url)?,
+                };
+                sasl_gssapi_bind(ldap, &server_fqdn).await?;
+            },
+            Self::SaslExternal => {
+                ldap.sasl_external_bind()
+                    .await.and_then(|r| r.success())
+                    .map_err(Error::ldap("SASL EXTERNAL bind"))?;
+            },
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(feature = "gssapi")]
+async fn sasl_gssapi_bind(ldap: &mut Ldap, server_fqdn: &str) -> Result<(), Error> {
+    ldap.sasl_gssapi_bind(server_fqdn)
+        .await.and_then(|r| r.success())
+        .map_err(Error::ldap("SASL GSSAPI bind"))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "gssapi"))]
+async fn sasl_gssapi_bind(_ldap: &mut Ldap, _server_fqdn: &str) -> Result<(), Error> {
+    Err(Error::Usage("GSSAPI support has not been enabled; rebuild with `--features gssapi`".to_owned()))
+}
+
+
+/// Checks whether the given login has the form `DOMAIN\user` expected by the NTLM bind.
+fn check_ntlm_login(login: &str) -> Result<(), Error> {
+    let valid = match login.split_once('\\') {
+        Some((domain, user)) => {
+            !domain.is_empty() && !user.is_empty()
+                && !domain.contains('@') && !user.contains(['\\', '@'])
+        },
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Usage(format!("NTLM bind requires a login of the form `DOMAIN\\user`, not {:?}", login)))
+    }
+}
+
+
+/// Extracts the host name from an LDAP URL.
+fn host_from_url(url: &str) -> Result<String, Error> {
+    let parsed = url::Url::parse(url)
+        .map_err(|e| Error::Parse {
+            context: format!("failed to parse LDAP URL {:?}", url),
+            message: e.to_string(),
+        })?;
+    match parsed.host_str() {
+        Some(h) => Ok(h.to_owned()),
+        None => Err(Error::Usage(format!("LDAP URL {:?} does not contain a host name; please specify --sasl-host", url))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::check_ntlm_login;
+
+    #[test]
+    fn test_check_ntlm_login() {
+        assert!(check_ntlm_login("EXAMPLE\\administrator").is_ok());
+        assert!(check_ntlm_login("administrator").is_err());
+        assert!(check_ntlm_login("administrator@example.com").is_err());
+        assert!(check_ntlm_login("\\administrator").is_err());
+        assert!(check_ntlm_login("EXAMPLE\\").is_err());
+        assert!(check_ntlm_login("EXAMPLE\\admin\\istrator").is_err());
+        assert!(check_ntlm_login("EXAMPLE\\administrator@example.com").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::opts::{
+    BindMethod, ConnectionOpts, LdapScope, Opts, OutputFormat, OutputOpts, parse_dns_server, SyncOpts,
+    WatchOpts,
+};
+
+
+/// The contents of the configuration file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+impl Config {
+    /// The path of the configuration file, usually `~/.config/adldapsearch/config.toml`.
+    pub fn path() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("adldapsearch");
+        path.push("config.toml");
+        Some(path)
+    }
+
+    /// Loads the configuration file.
+    pub fn load() -> Result<Self, Error> {
+        let path = Self::path()
+            .ok_or_else(|| Error::Usage("failed to determine the configuration directory".to_owned()))?;
+        let config_string = std::fs::read_to_string(&path)
+            .map_err(|error| Error::Io {
+                context: format!("failed to read configuration file {}", path.display()),
+                error,
+            })?;
+        toml::from_str(&config_string)
+            .map_err(|e| Error::Parse {
+                context: format!("failed to parse configuration file {}", path.display()),
+                message: e.to_string(),
+            })
+    }
+}
+
+
+/// A named set of default options for connecting to a server and searching it.
+///
+/// Each field corresponds to the command-line option of the same name; options given on the
+/// command line take precedence.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    pub url: Option<String>,
+    pub domain: Option<String>,
+    pub global_catalog: Option<bool>,
+    pub dns_server: Option<String>,
+    pub connect_timeout: Option<u64>,
+    pub base_dn: Option<String>,
+    pub scope: Option<LdapScope>,
+    pub bind_method: Option<BindMethod>,
+    pub bind_dn: Option<String>,
+    pub credentials_file: Option<PathBuf>,
+    pub password_env: Option<String>,
+    pub password_command: Option<String>,
+    pub password_keyring: Option<bool>,
+    pub sasl_host: Option<String>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    pub ca_file: Option<PathBuf>,
+    pub starttls: Option<bool>,
+    pub tls_insecure: Option<bool>,
+    pub tls_server_name: Option<String>,
+    pub sd_flags: Option<String>,
+    pub paginate: Option<i32>,
+    pub output: Option<OutputFormat>,
+}
+impl Profile {
+    /// Fills in the options of a search that have not been given on the command line from this
+    /// profile.
+    pub fn apply_to(&self, o: &mut Opts, matches: &ArgMatches) -> Result<(), Error> {
+        self.apply_to_connection(&mut o.connection, matches)?;
+
+        if o.sd_flags.is_none() {
+            if let Some(sd_flags) = self.sd_flags.as_ref() {
+                let parsed = sd_flags.parse()
+                    .map_err(|message| Error::Parse { context: "invalid sd_flags in profile".to_owned(), message })?;
+                o.sd_flags = Some(parsed);
+            }
+        }
+        fill(&mut o.base_dn, &self.base_dn);
+        if o.asq.is_none() {
+            // attribute scoped queries are always base searches
+            fill(&mut o.scope, &self.scope);
+        }
+        if o.vlv.is_none() {
+            // paging and VLV cannot be combined
+            fill(&mut o.paginate, &self.paginate);
+        }
+        self.apply_to_output(&mut o.output, matches);
+        Ok(())
+    }
+
+    /// Fills in the options of a synchronization that have not been given on the command line
+    /// from this profile.
+    pub fn apply_to_sync(&self, o: &mut SyncOpts, matches: &ArgMatches) -> Result<(), Error> {
+        self.apply_to_connection(&mut o.connection, matches)?;
+        fill(&mut o.base_dn, &self.base_dn);
+        self.apply_to_output(&mut o.output, matches);
+        Ok(())
+    }
+
+    /// Fills in the options of a watch that have not been given on the command line from this
+    /// profile.
+    pub fn apply_to_watch(&self, o: &mut WatchOpts, matches: &ArgMatches) -> Result<(), Error> {
+        self.apply_to_connection(&mut o.connection, matches)?;
+        fill(&mut o.base_dn, &self.base_dn);
+        self.apply_to_output(&mut o.output, matches);
+        Ok(())
+    }
+
+    fn apply_to_connection(&self, o: &mut ConnectionOpts, matches: &ArgMatches) -> Result<(), Error> {
+        if o.url.is_none() && o.domain.is_none() {
+            o.url.clone_from(&self.url);
+            o.domain.clone_from(&self.domain);
+            o.global_catalog |= self.global_catalog.unwrap_or(false);
+        }
+        if o.dns_server.is_none() {
+            if let Some(dns_server) = self.dns_server.as_ref() {
+                let parsed = parse_dns_server(dns_server)
+                    .map_err(|message| Error::Parse { context: "invalid dns_server in profile".to_owned(), message })?;
+                o.dns_server = Some(parsed);
+            }
+        }
+        fill(&mut o.sasl_host, &self.sasl_host);
+        fill(&mut o.ca_file, &self.ca_file);
+        fill(&mut o.tls_server_name, &self.tls_server_name);
+
+        if o.client_cert.is_none() && o.client_key.is_none() {
+            o.client_cert.clone_from(&self.client_cert);
+            o.client_key.clone_from(&self.client_key);
+        }
+
+        if !given_on_command_line(matches, "connect_timeout") {
+            match self.connect_timeout {
+                Some(0) => return Err(Error::Parse {
+                    context: "invalid connect_timeout in profile".to_owned(),
+                    message: "the timeout must be at least one second".to_owned(),
+                }),
+                Some(connect_timeout) => o.connect_timeout = connect_timeout,
+                None => {},
+            }
+        }
+
+        if !given_on_command_line(matches, "bind_method") && !o.anonymous {
+            if let Some(bind_method) = self.bind_method {
+                o.bind_method = bind_method;
+            }
+        }
+
+        // the bind DN, credentials file and password source are taken together from the
+        // profile or not at all, so that they cannot be mixed up
+        let credentials_given = o.bind_dn.is_some() || o.credentials_file.is_some();
+        let password_source_given = o.password_env.is_some()
+            || o.password_command.is_some()
+            || o.password_keyring;
+        if !credentials_given && !password_source_given {
+            o.bind_dn.clone_from(&self.bind_dn);
+            o.credentials_file.clone_from(&self.credentials_file);
+            o.password_env.clone_from(&self.password_env);
+            o.password_command.clone_from(&self.password_command);
+            o.password_keyring = self.password_keyring.unwrap_or(false);
+        }
+
+        // --no-starttls and --no-tls-insecure switch off what the profile switches on
+        if !o.starttls && !o.no_starttls {
+            o.starttls = self.starttls.unwrap_or(false);
+        }
+        if !o.tls_insecure && !o.no_tls_insecure {
+            o.tls_insecure = self.tls_insecure.unwrap_or(false);
+        }
+        Ok(())
+    }
+
+    fn apply_to_output(&self, o: &mut OutputOpts, matches: &ArgMatches) {
+        if !given_on_command_line(matches, "format") {
+            if let Some(output) = self.output {
+                o.format = output;
+            }
+        }
+    }
+}
+
+
+fn fill<T: Clone>(opt: &mut Option<T>, profile_value: &Option<T>) {
+    if opt.is_none() {
+        opt.clone_from(profile_value);
+    }
+}
+
+
+fn given_on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+
+/// Loads the profile with the given name from the configuration file.
+pub(crate) fn load_profile(name: &str) -> Result<Profile, Error> {
+    let mut config = Config::load()?;
+    config.profiles.remove(name)
+        .ok_or_else(|| Error::Usage(format!("profile {:?} not found in configuration file", name)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::{Config, Profile};
+    use crate::opts::{BindMethod, LdapScope, Opts, OutputFormat};
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(r#"
+            [profiles.corp-gc]
+            url = "ldaps://gc.corp.example.com:3269/"
+            base_dn = "dc=corp,dc=example,dc=com"
+            scope = "subtree"
+            bind_method = "simple"
+            bind_dn = "CORP\\svc-ldap"
+            password_command = "pass show ad/svc-ldap"
+            sd_flags = "owner,group,dacl"
+            paginate = 500
+            output = "json-lines"
+
+            [profiles.lab]
+            url = "ldap://dc01.lab.example.com/"
+            bind_method = "anonymous"
+        "#).unwrap();
+
+        assert_eq!(config.profiles.len(), 2);
+        let corp_gc = &config.profiles["corp-gc"];
+        assert_eq!(corp_gc.url.as_deref(), Some("ldaps://gc.corp.example.com:3269/"));
+        assert_eq!(corp_gc.scope, Some(LdapScope::Subtree));
+        assert_eq!(corp_gc.bind_method, Some(BindMethod::Simple));
+        assert_eq!(corp_gc.bind_dn.as_deref(), Some("CORP\\svc-ldap"));
+        assert_eq!(corp_gc.sd_flags.as_deref(), Some("owner,group,dacl"));
+        assert_eq!(corp_gc.paginate, Some(500));
+        assert_eq!(corp_gc.output, Some(OutputFormat::JsonLines));
+        assert_eq!(config.profiles["lab"].bind_method, Some(BindMethod::Anonymous));
+    }
+
+    #[test]
+    fn test_credentials_not_mixed() {
+        let profile: Profile = toml::from_str(r#"
+            bind_dn = "CORP\\svc-ldap"
+            password_command = "pass show ad/svc-ldap"
+        "#).unwrap();
+
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert_eq!(o.connection.bind_dn.as_deref(), Some("CORP\\svc-ldap"));
+        assert_eq!(o.connection.password_command.as_deref(), Some("pass show ad/svc-ldap"));
+
+        // another account must not be combined with the password of the profile's account
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base", "-D", "cn=other,dc=example,dc=com"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert_eq!(o.connection.bind_dn.as_deref(), Some("cn=other,dc=example,dc=com"));
+        assert_eq!(o.connection.password_command, None);
+
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base", "--password-env", "LDAP_PASSWORD"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert_eq!(o.connection.bind_dn, None);
+        assert_eq!(o.connection.password_command, None);
+    }
+
+    #[test]
+    fn test_tls_overridden() {
+        let profile: Profile = toml::from_str(r#"
+            starttls = true
+            tls_insecure = true
+        "#).unwrap();
+
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert!(o.connection.starttls);
+        assert!(o.connection.tls_insecure);
+
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base", "--no-starttls", "--no-tls-insecure"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert!(!o.connection.starttls);
+        assert!(!o.connection.tls_insecure);
+
+        // the last of the flags wins
+        let matches = Opts::command().get_matches_from(["adldapsearch", "-s", "base", "--no-starttls", "-Z"]);
+        let mut o = Opts::from_arg_matches(&matches).unwrap();
+        profile.apply_to(&mut o, &matches).unwrap();
+        assert!(o.connection.starttls);
+    }
+}
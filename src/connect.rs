@@ -0,0 +1,152 @@
+use ldap3::{Ldap, LdapConnAsync};
+
+use crate::bind::Authentication;
+use crate::discovery::discover_domain_controllers;
+use crate::error::Error;
+use crate::opts::ConnectionOpts;
+use crate::tls::{conn_settings_from_opts, verify_tls};
+
+
+/// Connects to the LDAP server with the given URL and binds to it.
+async fn connect_to_url(o: &ConnectionOpts, url: &str, authentication: &Authentication) -> Result<Ldap, Error> {
+    if o.verbose {
+        eprintln!("connecting to {}", url);
+    }
+
+    let conn_settings = conn_settings_from_opts(o)?;
+    let (conn, mut ldap) = LdapConnAsync::with_settings(conn_settings, url)
+        .await.map_err(Error::connect)?;
+    ldap3::drive!(conn);
+    verify_tls(&mut ldap, o, url).await?;
+
+    authentication.bind(&mut ldap, url).await?;
+    Ok(ldap)
+}
+
+
+/// Connects to the LDAP server specified in the command-line options and binds to it.
+///
+/// If the server is specified using `--domain`, the domain controllers found in DNS are tried in
+/// turn until one of them can be connected and bound to.
+pub(crate) async fn connect(o: &ConnectionOpts) -> Result<Ldap, Error> {
+    // this may query the user for a password; do it before connecting
+    let authentication = Authentication::from_opts(o)?;
+    connect_with(o, &authentication).await
+}
+
+
+/// Connects to the LDAP server specified in the command-line options and binds to it using the
+/// given authentication information, e.g. to reconnect without querying the password again.
+pub(crate) async fn connect_with(o: &ConnectionOpts, authentication: &Authentication) -> Result<Ldap, Error> {
+    let urls = if let Some(url) = o.url.as_ref() {
+        vec![url.clone()]
+    } else if let Some(domain) = o.domain.as_ref() {
+        let targets = discover_domain_controllers(domain, o.global_catalog, o.dns_server).await?;
+        if o.verbose {
+            for target in &targets {
+                eprintln!(
+                    "found domain controller {}:{} (priority {}, weight {})",
+                    target.host, target.port, target.priority, target.weight,
+                );
+            }
+        }
+        targets.iter()
+            .map(|t| t.url())
+            .collect()
+    } else {
+        return Err(Error::Usage("one of -H/--url and --domain must be given, either directly or through --profile".to_owned()));
+    };
+    connect_to_any(o, &urls, authentication).await
+}
+
+
+/// Connects to the LDAP servers with the given URLs in turn until one of them can be connected and
+/// bound to.
+async fn connect_to_any(o: &ConnectionOpts, urls: &[String], authentication: &Authentication) -> Result<Ldap, Error> {
+    // reported if there are no URLs to try
+    let mut last_error = Error::Discovery("no domain controllers found".to_owned());
+    for url in urls {
+        match connect_to_url(o, url, authentication).await {
+            Ok(ldap) => return Ok(ldap),
+            Err(e) if e.is_server_unavailable() => {
+                if urls.len() > 1 {
+                    eprintln!("adldapsearch: {}: {}", url, e);
+                }
+                last_error = e;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use clap::Parser;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::connect_to_any;
+    use crate::bind::Authentication;
+    use crate::opts::Opts;
+    use crate::tls::tests::use_test_system_ca;
+
+    #[tokio::test]
+    async fn test_failover_after_timeout() {
+        use_test_system_ca();
+
+        // accepts TCP connections but never completes the TLS handshake, like a DC that hangs
+        let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let silent_port = silent.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            loop {
+                let (stream, _addr) = silent.accept().await.unwrap();
+                connections.push(stream);
+            }
+        });
+
+        // answers every request with a successful bind response
+        let stub = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stub_port = stub.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _addr) = stub.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 256];
+                    while let Ok(length) = stream.read(&mut buf).await {
+                        if length < 5 {
+                            break;
+                        }
+                        // SEQUENCE { messageID, bindResponse { success, "", "" } }
+                        let message_id = buf[4];
+                        let response = [0x30, 0x0C, 0x02, 0x01, message_id, 0x61, 0x07, 0x0A, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00];
+                        if stream.write_all(&response).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let o = Opts::try_parse_from(["adldapsearch", "--anonymous", "--connect-timeout", "1"])
+            .unwrap()
+            .connection;
+        let urls = [
+            format!("ldaps://127.0.0.1:{}/", silent_port),
+            format!("ldap://127.0.0.1:{}/", stub_port),
+        ];
+        let start = Instant::now();
+        tokio::time::timeout(Duration::from_secs(10), connect_to_any(&o, &urls, &Authentication::Anonymous))
+            .await.expect("connection attempt did not time out")
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // without another server, the timeout is reported as a connection error
+        let error = connect_to_any(&o, &urls[0..1], &Authentication::Anonymous).await.unwrap_err();
+        assert!(error.is_server_unavailable());
+    }
+}
@@ -0,0 +1,665 @@
+use std::str::FromStr;
+
+use bitflags::bitflags;
+use ldap3::asn1::{parse_tag, parse_uint, StructureTag, TagClass, Types};
+use ldap3::{LdapError, LdapResult};
+use ldap3::controls::{Control, RawControl};
+
+use crate::ber::Ber;
+use crate::error::Error;
+use crate::opts::{ExtendedDnFormat, Opts};
+
+
+pub(crate) const SHOW_DELETED_OID: &str = "1.2.840.113556.1.4.417";
+pub(crate) const NOTIFICATION_OID: &str = "1.2.840.113556.1.4.528";
+pub(crate) const EXTENDED_DN_OID: &str = "1.2.840.113556.1.4.529";
+pub(crate) const SD_FLAGS_OID: &str = "1.2.840.113556.1.4.801";
+pub(crate) const DIRSYNC_OID: &str = "1.2.840.113556.1.4.841";
+pub(crate) const DOMAIN_SCOPE_OID: &str = "1.2.840.113556.1.4.1339";
+pub(crate) const SEARCH_OPTIONS_OID: &str = "1.2.840.113556.1.4.1340";
+pub(crate) const ASQ_OID: &str = "1.2.840.113556.1.4.1504";
+pub(crate) const SHOW_RECYCLED_OID: &str = "1.2.840.113556.1.4.2064";
+pub(crate) const SHOW_DEACTIVATED_LINK_OID: &str = "1.2.840.113556.1.4.2065";
+pub(crate) const SORT_REQUEST_OID: &str = "1.2.840.113556.1.4.473";
+pub(crate) const SORT_RESPONSE_OID: &str = "1.2.840.113556.1.4.474";
+pub(crate) const VLV_REQUEST_OID: &str = "2.16.840.1.113730.3.4.9";
+pub(crate) const VLV_RESPONSE_OID: &str = "2.16.840.1.113730.3.4.10";
+
+
+/// A sort key for the server-side sort control (RFC2891).
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct SortKey {
+    pub attribute: String,
+    pub ordering_rule: Option<String>,
+    pub reverse: bool,
+}
+impl FromStr for SortKey {
+    type Err = String;
+
+    /// Parses a sort key in the format `attribute[:orderingRule][-]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rest, reverse) = match s.strip_suffix('-') {
+            Some(r) => (r, true),
+            None => (s, false),
+        };
+        let (attribute, ordering_rule) = match rest.split_once(':') {
+            Some((a, r)) => (a, Some(r.to_owned())),
+            None => (rest, None),
+        };
+        if attribute.is_empty() {
+            return Err(format!("sort key {:?} does not contain an attribute name", s));
+        }
+        if ordering_rule.as_deref() == Some("") {
+            return Err(format!("sort key {:?} contains an empty ordering rule", s));
+        }
+        Ok(Self {
+            attribute: attribute.to_owned(),
+            ordering_rule,
+            reverse,
+        })
+    }
+}
+
+
+/// Creates a server-side sort request control (RFC2891) sorting by the given keys.
+pub(crate) fn sort_control(keys: &[SortKey]) -> RawControl {
+    // SortKeyList ::= SEQUENCE OF SEQUENCE {
+    //     attributeType   AttributeDescription,
+    //     orderingRule    [0] MatchingRuleId OPTIONAL,
+    //     reverseOrder    [1] BOOLEAN DEFAULT FALSE }
+    let key_list = keys.iter()
+        .map(|key| {
+            let mut key_contents = vec![Ber::OctetString(key.attribute.as_bytes().to_vec())];
+            if let Some(ordering_rule) = key.ordering_rule.as_ref() {
+                key_contents.push(Ber::ContextPrimitive(0, ordering_rule.as_bytes().to_vec()));
+            }
+            if key.reverse {
+                key_contents.push(Ber::context_boolean(1, true));
+            }
+            Ber::Sequence(key_contents)
+        })
+        .collect();
+    RawControl {
+        ctype: SORT_REQUEST_OID.to_owned(),
+        crit: false,
+        val: Some(Ber::Sequence(key_list).encode()),
+    }
+}
+
+
+/// The parameters of a Virtual List View request.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct VlvRequest {
+    /// The one-based position of the target entry within the sorted result set.
+    pub offset: u32,
+
+    /// The number of entries to return before the target entry.
+    pub before_count: u32,
+
+    /// The number of entries to return after the target entry.
+    pub after_count: u32,
+}
+impl FromStr for VlvRequest {
+    type Err = String;
+
+    /// Parses VLV parameters in the format `offset,before,after`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pieces: Vec<&str> = s.split(',').collect();
+        if pieces.len() != 3 {
+            return Err(format!("{:?} is not in the format OFFSET,BEFORE,AFTER", s));
+        }
+        let numbers: Vec<u32> = pieces.iter()
+            .map(|p| p.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("{:?} is not in the format OFFSET,BEFORE,AFTER", s))?;
+        if numbers[0] == 0 {
+            return Err("the VLV offset is one-based and must not be 0".to_owned());
+        }
+        Ok(Self {
+            offset: numbers[0],
+            before_count: numbers[1],
+            after_count: numbers[2],
+        })
+    }
+}
+
+
+/// Creates a Virtual List View request control (draft-ietf-ldapext-ldapv3-vlv-09).
+pub(crate) fn vlv_control(vlv: &VlvRequest) -> RawControl {
+    // VirtualListViewRequest ::= SEQUENCE {
+    //     beforeCount    INTEGER (0..maxInt),
+    //     afterCount     INTEGER (0..maxInt),
+    //     target       CHOICE {
+    //         byOffset        [0] SEQUENCE {
+    //             offset          INTEGER (1 .. maxInt),
+    //             contentCount    INTEGER (0 .. maxInt) },
+    //         greaterThanOrEqual [1] AssertionValue },
+    //     contextID     OCTET STRING OPTIONAL }
+    let request = Ber::Sequence(vec![
+        Ber::Integer(vlv.before_count.into()),
+        Ber::Integer(vlv.after_count.into()),
+        Ber::ContextConstructed(0, vec![
+            Ber::Integer(vlv.offset.into()),
+            // content count 0: let the server use its own estimate
+            Ber::Integer(0),
+        ]),
+    ]);
+    RawControl {
+        ctype: VLV_REQUEST_OID.to_owned(),
+        crit: true,
+        val: Some(request.encode()),
+    }
+}
+
+
+/// Search option flag extending the search to all naming contexts below the base DN.
+const SERVER_SEARCH_FLAG_PHANTOM_ROOT: i64 = 0x2;
+
+
+bitflags! {
+    /// The parts of a security descriptor to request (`SECURITY_INFORMATION`).
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub(crate) struct SdFlags : u32 {
+        const Owner = 0x1;
+        const Group = 0x2;
+        const Dacl = 0x4;
+        const Sacl = 0x8;
+    }
+}
+impl FromStr for SdFlags {
+    type Err = String;
+
+    /// Parses a comma-separated list of the parts `owner`, `group`, `dacl` and `sacl`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = Self::empty();
+        for part in s.split(',') {
+            flags |= match part.trim().to_lowercase().as_str() {
+                "owner" => Self::Owner,
+                "group" => Self::Group,
+                "dacl" => Self::Dacl,
+                "sacl" => Self::Sacl,
+                _ => return Err(format!("unknown security descriptor part {:?}; expected owner, group, dacl or sacl", part)),
+            };
+        }
+        Ok(flags)
+    }
+}
+
+
+/// Creates a control that only requests the given parts of `nTSecurityDescriptor`.
+pub(crate) fn sd_flags_control(flags: SdFlags) -> RawControl {
+    // SDFlagsRequestValue ::= SEQUENCE { Flags INTEGER }
+    RawControl {
+        ctype: SD_FLAGS_OID.to_owned(),
+        crit: false,
+        val: Some(Ber::Sequence(vec![Ber::Integer(flags.bits().into())]).encode()),
+    }
+}
+
+
+/// Creates a control without a value.
+fn flag_control(oid: &str) -> RawControl {
+    RawControl {
+        ctype: oid.to_owned(),
+        crit: true,
+        val: None,
+    }
+}
+
+
+/// Creates a control turning a search into a persistent search that returns entries whenever they
+/// are modified.
+pub(crate) fn notification_control() -> RawControl {
+    flag_control(NOTIFICATION_OID)
+}
+
+
+/// Creates a control that makes deleted objects visible.
+pub(crate) fn show_deleted_control() -> RawControl {
+    flag_control(SHOW_DELETED_OID)
+}
+
+
+/// A control given verbatim on the command line.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct CustomControl {
+    pub oid: String,
+    pub critical: bool,
+    pub value: Option<Vec<u8>>,
+}
+impl CustomControl {
+    pub fn to_raw_control(&self) -> RawControl {
+        RawControl {
+            ctype: self.oid.clone(),
+            crit: self.critical,
+            val: self.value.clone(),
+        }
+    }
+}
+impl FromStr for CustomControl {
+    type Err = String;
+
+    /// Parses a control in the format `OID[:critical][:value]`, where the value is given in
+    /// hexadecimal.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = s.splitn(3, ':');
+        let oid = pieces.next().unwrap();
+        if oid.is_empty() || !oid.split('.').all(|arc| !arc.is_empty() && arc.bytes().all(|b| b.is_ascii_digit())) {
+            return Err(format!("{:?} is not a valid OID", oid));
+        }
+
+        let (critical, value_hex) = match (pieces.next(), pieces.next()) {
+            (None, _) => (false, None),
+            (Some("critical"), value) => (true, value),
+            (Some(value), None) => (false, Some(value)),
+            (Some(_), Some(_)) => return Err(format!("{:?} is not in the format OID[:critical][:value]", s)),
+        };
+        let value = match value_hex {
+            Some(vh) => Some(
+                crate::hex_to_bytes(vh)
+                    .ok_or_else(|| format!("control value {:?} is not valid hexadecimal", vh))?
+            ),
+            None => None,
+        };
+        Ok(Self {
+            oid: oid.to_owned(),
+            critical,
+            value,
+        })
+    }
+}
+
+
+/// Creates the request controls selected by the command-line options.
+pub(crate) fn request_controls(o: &Opts) -> Vec<RawControl> {
+    let mut controls = entry_controls(o);
+    if o.domain_scope {
+        controls.push(flag_control(DOMAIN_SCOPE_OID));
+    }
+    if o.phantom_root {
+        // SearchOptionsRequestValue ::= SEQUENCE { Flags INTEGER }
+        controls.push(RawControl {
+            ctype: SEARCH_OPTIONS_OID.to_owned(),
+            crit: true,
+            val: Some(Ber::Sequence(vec![Ber::Integer(SERVER_SEARCH_FLAG_PHANTOM_ROOT)]).encode()),
+        });
+    }
+    if let Some(source_attribute) = o.asq.as_ref() {
+        controls.push(asq_control(source_attribute));
+    }
+    if !o.sort.is_empty() {
+        controls.push(sort_control(&o.sort));
+    }
+    if let Some(vlv) = o.vlv.as_ref() {
+        controls.push(vlv_control(vlv));
+    }
+    controls.extend(o.control.iter().map(|c| c.to_raw_control()));
+    controls
+}
+
+
+/// Creates the request controls selected by the command-line options that determine which entries
+/// are visible and how their values are returned.
+///
+/// These are also passed to the searches retrieving the remaining values of ranged attributes, so
+/// that e.g. deleted objects can be found again and their values are returned in the same format.
+pub(crate) fn entry_controls(o: &Opts) -> Vec<RawControl> {
+    let mut controls = Vec::new();
+    if let Some(sd_flags) = o.sd_flags {
+        controls.push(sd_flags_control(sd_flags));
+    }
+    if o.show_deleted {
+        controls.push(show_deleted_control());
+    }
+    if o.show_recycled {
+        controls.push(flag_control(SHOW_RECYCLED_OID));
+    }
+    if o.show_deactivated_links {
+        controls.push(flag_control(SHOW_DEACTIVATED_LINK_OID));
+    }
+    if let Some(format) = o.extended_dn {
+        // ExtendedDNRequestValue ::= SEQUENCE { Flag INTEGER }
+        let flag = match format {
+            ExtendedDnFormat::Hex => 0,
+            ExtendedDnFormat::String => 1,
+        };
+        controls.push(RawControl {
+            ctype: EXTENDED_DN_OID.to_owned(),
+            crit: false,
+            val: Some(Ber::Sequence(vec![Ber::Integer(flag)]).encode()),
+        });
+    }
+    controls
+}
+
+
+fn parse_universal_uint(tag: StructureTag, universal_type: Types) -> Option<u64> {
+    let bytes = tag
+        .match_class(TagClass::Universal)?
+        .match_id(universal_type as u64)?
+        .expect_primitive()?;
+    let (_rest, value) = parse_uint(&bytes).ok()?;
+    Some(value)
+}
+
+
+/// The response to a server-side sort request.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct SortResponse {
+    pub result: u64,
+    pub attribute: Option<String>,
+}
+impl SortResponse {
+    pub fn parse(val: &[u8]) -> Option<Self> {
+        let (_rest, tag) = parse_tag(val).ok()?;
+        let mut components = tag.expect_constructed()?.into_iter();
+        let result = parse_universal_uint(components.next()?, Types::Enumerated)?;
+        let attribute = match components.next() {
+            Some(t) => {
+                let bytes = t
+                    .match_class(TagClass::Context)?
+                    .match_id(0)?
+                    .expect_primitive()?;
+                Some(String::from_utf8(bytes).ok()?)
+            },
+            None => None,
+        };
+        Some(Self { result, attribute })
+    }
+}
+
+
+/// The response to a Virtual List View request.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct VlvResponse {
+    pub target_position: u64,
+    pub content_count: u64,
+    pub result: u64,
+}
+impl VlvResponse {
+    pub fn parse(val: &[u8]) -> Option<Self> {
+        let (_rest, tag) = parse_tag(val).ok()?;
+        let mut components = tag.expect_constructed()?.into_iter();
+        let target_position = parse_universal_uint(components.next()?, Types::Integer)?;
+        let content_count = parse_universal_uint(components.next()?, Types::Integer)?;
+        let result = parse_universal_uint(components.next()?, Types::Enumerated)?;
+        // ignore the context ID; we do not continue VLV searches
+        Some(Self { target_position, content_count, result })
+    }
+}
+
+
+/// Creates an Attribute Scoped Query control, which makes a base search return the entries
+/// referenced by the given DN-valued attribute of the base entry instead of the base entry itself.
+pub(crate) fn asq_control(source_attribute: &str) -> RawControl {
+    // ASQRequestValue ::= SEQUENCE { sourceAttribute OCTET STRING }
+    RawControl {
+        ctype: ASQ_OID.to_owned(),
+        crit: true,
+        val: Some(Ber::Sequence(vec![Ber::OctetString(source_attribute.as_bytes().to_vec())]).encode()),
+    }
+}
+
+
+/// DirSync flag returning only the objects and attributes that the bound user may read.
+pub(crate) const DIRSYNC_OBJECT_SECURITY: i64 = 0x1;
+
+/// DirSync flag returning parent objects before their children.
+pub(crate) const DIRSYNC_ANCESTORS_FIRST_ORDER: i64 = 0x800;
+
+
+/// Creates a DirSync request control continuing from the given cookie (empty for a full
+/// synchronization).
+pub(crate) fn dirsync_control(flags: i64, cookie: &[u8]) -> RawControl {
+    // DirSyncRequestValue ::= SEQUENCE {
+    //     Flags       INTEGER,
+    //     MaxBytes    INTEGER,
+    //     Cookie      OCTET STRING }
+    let request = Ber::Sequence(vec![
+        Ber::Integer(flags),
+        Ber::Integer(i32::MAX.into()),
+        Ber::OctetString(cookie.to_vec()),
+    ]);
+    RawControl {
+        ctype: DIRSYNC_OID.to_owned(),
+        crit: true,
+        val: Some(request.encode()),
+    }
+}
+
+
+/// The response to a DirSync request.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct DirSyncResponse {
+    pub more_results: bool,
+    pub cookie: Vec<u8>,
+}
+impl DirSyncResponse {
+    pub fn parse(val: &[u8]) -> Option<Self> {
+        // DirSyncResponseValue ::= SEQUENCE {
+        //     MoreResults     INTEGER,
+        //     unused          INTEGER,
+        //     CookieServer    OCTET STRING }
+        let (_rest, tag) = parse_tag(val).ok()?;
+        let mut components = tag.expect_constructed()?.into_iter();
+        let more_results = parse_universal_uint(components.next()?, Types::Integer)?;
+        let _unused = components.next()?;
+        let cookie = components.next()?
+            .match_class(TagClass::Universal)?
+            .match_id(Types::OctetString as u64)?
+            .expect_primitive()?;
+        Some(Self { more_results: more_results != 0, cookie })
+    }
+
+    /// Finds and parses the DirSync response among the given controls.
+    pub fn find(controls: &[Control]) -> Option<Self> {
+        controls.iter()
+            .find(|Control(_control_type, raw)| raw.ctype == DIRSYNC_OID)
+            .and_then(|Control(_control_type, raw)| Self::parse(raw.val.as_deref()?))
+    }
+}
+
+
+/// The response to an Attribute Scoped Query.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct AsqResponse {
+    pub result: u64,
+}
+impl AsqResponse {
+    pub fn parse(val: &[u8]) -> Option<Self> {
+        // ASQResponseValue ::= SEQUENCE { searchResult ENUMERATED }
+        let (_rest, tag) = parse_tag(val).ok()?;
+        let mut components = tag.expect_constructed()?.into_iter();
+        let result = parse_universal_uint(components.next()?, Types::Enumerated)?;
+        Some(Self { result })
+    }
+}
+
+
+/// Returns an error if the server has reported a failure in the response to an Attribute Scoped
+/// Query.
+///
+/// The search itself succeeds even if the query fails, but returns no entries.
+pub(crate) fn check_asq_response(controls: &[Control]) -> Result<(), Error> {
+    let Some(Control(_control_type, raw)) = controls.iter().find(|Control(_ct, raw)| raw.ctype == ASQ_OID) else {
+        return Ok(());
+    };
+    let response = raw.val.as_deref()
+        .and_then(AsqResponse::parse)
+        .ok_or_else(|| Error::Parse {
+            context: "failed to check the result of the attribute scoped query".to_owned(),
+            message: "the server returned an invalid ASQ response control".to_owned(),
+        })?;
+    let text = match response.result {
+        0 => return Ok(()),
+        21 => "the source attribute does not contain Distinguished Names",
+        53 => "the server is unwilling to perform the query; the scope must be base",
+        71 => "the referenced entries are in other domains; query a global catalog server",
+        _ => "",
+    };
+    let result = LdapResult {
+        rc: response.result.try_into().unwrap_or(u32::MAX),
+        matched: String::new(),
+        text: text.to_owned(),
+        refs: Vec::new(),
+        ctrls: Vec::new(),
+    };
+    Err(Error::ldap("attribute scoped query")(LdapError::LdapResult { result }))
+}
+
+
+/// Outputs the information contained in the sort and VLV response controls to standard error.
+pub(crate) fn report_response_controls(controls: &[Control]) {
+    for Control(_control_type, raw) in controls {
+        let Some(val) = raw.val.as_deref() else { continue };
+        if raw.ctype == SORT_RESPONSE_OID {
+            match SortResponse::parse(val) {
+                Some(sr) if sr.result != 0 => {
+                    match sr.attribute {
+                        Some(attribute) => eprintln!("adldapsearch: server-side sorting by {} failed (result code {})", attribute, sr.result),
+                        None => eprintln!("adldapsearch: server-side sorting failed (result code {})", sr.result),
+                    }
+                },
+                Some(_) => {},
+                None => eprintln!("adldapsearch: failed to parse sort response control"),
+            }
+        } else if raw.ctype == VLV_RESPONSE_OID {
+            match VlvResponse::parse(val) {
+                Some(vr) => {
+                    eprintln!("adldapsearch: VLV target position {}, content count {}", vr.target_position, vr.content_count);
+                    if vr.result != 0 {
+                        eprintln!("adldapsearch: virtual list view failed (result code {})", vr.result);
+                    }
+                },
+                None => eprintln!("adldapsearch: failed to parse VLV response control"),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_control() {
+        let keys = [
+            "cn-".parse::<SortKey>().unwrap(),
+            "sn:2.5.13.3".parse::<SortKey>().unwrap(),
+        ];
+        let control = sort_control(&keys);
+        assert_eq!(control.ctype, SORT_REQUEST_OID);
+        assert_eq!(
+            control.val.unwrap(),
+            [
+                0x30, 0x19,
+                    0x30, 0x07,
+                        0x04, 0x02, b'c', b'n',
+                        0x81, 0x01, 0xFF,
+                    0x30, 0x0E,
+                        0x04, 0x02, b's', b'n',
+                        0x80, 0x08, b'2', b'.', b'5', b'.', b'1', b'3', b'.', b'3',
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vlv_control() {
+        let vlv: VlvRequest = "1,0,199".parse().unwrap();
+        assert_eq!(
+            vlv_control(&vlv).val.unwrap(),
+            [
+                0x30, 0x0F,
+                    0x02, 0x01, 0x00,
+                    0x02, 0x02, 0x00, 0xC7,
+                    0xA0, 0x06,
+                        0x02, 0x01, 0x01,
+                        0x02, 0x01, 0x00,
+            ],
+        );
+        assert!("0,0,10".parse::<VlvRequest>().is_err());
+        assert!("1,2".parse::<VlvRequest>().is_err());
+    }
+
+    #[test]
+    fn test_dirsync_control() {
+        assert_eq!(
+            dirsync_control(DIRSYNC_ANCESTORS_FIRST_ORDER, &[0xAB]).val.unwrap(),
+            [
+                0x30, 0x0D,
+                    0x02, 0x02, 0x08, 0x00,
+                    0x02, 0x04, 0x7F, 0xFF, 0xFF, 0xFF,
+                    0x04, 0x01, 0xAB,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_asq_control() {
+        assert_eq!(
+            asq_control("member").val.unwrap(),
+            [0x30, 0x08, 0x04, 0x06, b'm', b'e', b'm', b'b', b'e', b'r'],
+        );
+    }
+
+    #[test]
+    fn test_sd_flags() {
+        let flags: SdFlags = "owner, DACL".parse().unwrap();
+        assert_eq!(flags, SdFlags::Owner | SdFlags::Dacl);
+        assert_eq!(sd_flags_control(flags).val.unwrap(), [0x30, 0x03, 0x02, 0x01, 0x05]);
+        assert_eq!("sacl".parse::<SdFlags>().unwrap(), SdFlags::Sacl);
+        assert!("owner,label".parse::<SdFlags>().is_err());
+        assert!("".parse::<SdFlags>().is_err());
+    }
+
+    #[test]
+    fn test_custom_control() {
+        let control: CustomControl = "1.2.840.113556.1.4.801:critical:30 03 02 01 07".parse().unwrap();
+        assert_eq!(control.oid, SD_FLAGS_OID);
+        assert!(control.critical);
+        assert_eq!(
+            control.value.unwrap(),
+            sd_flags_control(SdFlags::Owner | SdFlags::Group | SdFlags::Dacl).val.unwrap(),
+        );
+
+        let control: CustomControl = "1.2.840.113556.1.4.417".parse().unwrap();
+        assert!(!control.critical);
+        assert_eq!(control.value, None);
+
+        let control: CustomControl = "1.2.3:0A0B".parse().unwrap();
+        assert!(!control.critical);
+        assert_eq!(control.value.unwrap(), [0x0A, 0x0B]);
+
+        assert!("1.2.3:critical:xyz".parse::<CustomControl>().is_err());
+        assert!("1.2.3:01:02".parse::<CustomControl>().is_err());
+        assert!("cn:critical".parse::<CustomControl>().is_err());
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let sort = SortResponse::parse(&[0x30, 0x07, 0x0A, 0x01, 0x10, 0x80, 0x02, b'c', b'n']).unwrap();
+        assert_eq!(sort, SortResponse { result: 16, attribute: Some("cn".to_owned()) });
+
+        let vlv = VlvResponse::parse(&[
+            0x30, 0x0A,
+                0x02, 0x01, 0x01,
+                0x02, 0x02, 0x04, 0xD2,
+                0x0A, 0x01, 0x00,
+        ]).unwrap();
+        assert_eq!(vlv, VlvResponse { target_position: 1, content_count: 1234, result: 0 });
+
+        let dirsync = DirSyncResponse::parse(&[
+            0x30, 0x0C,
+                0x02, 0x01, 0x01,
+                0x02, 0x01, 0x00,
+                0x04, 0x04, 0x4D, 0x53, 0x44, 0x53,
+        ]).unwrap();
+        assert_eq!(dirsync, DirSyncResponse { more_results: true, cookie: b"MSDS".to_vec() });
+
+        assert_eq!(
+            AsqResponse::parse(&[0x30, 0x03, 0x0A, 0x01, 0x47]).unwrap(),
+            AsqResponse { result: 71 },
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::process::{Command, Stdio};
+
+use crate::error::Error;
+use crate::opts::{ConnectionOpts, Credentials};
+
+
+/// The source from which the password for a simple bind is obtained.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum PasswordSource {
+    /// The password is given verbatim.
+    Literal(String),
+
+    /// The password is stored in the environment variable with the given name.
+    Environment(String),
+
+    /// The password is output by the given shell command.
+    Command(String),
+
+    /// The password is stored in the system keyring (Secret Service on Linux).
+    Keyring,
+
+    /// The password is queried from the terminal.
+    Prompt,
+}
+impl PasswordSource {
+    /// Collects the password source given on the command line, if any.
+    pub fn from_opts(o: &ConnectionOpts) -> Option<Self> {
+        if let Some(env_var) = o.password_env.as_ref() {
+            Some(Self::Environment(env_var.clone()))
+        } else if let Some(command) = o.password_command.as_ref() {
+            Some(Self::Command(command.clone()))
+        } else if o.password_keyring {
+            Some(Self::Keyring)
+        } else {
+            None
+        }
+    }
+
+    /// Collects the password source specified in a credentials file.
+    ///
+    /// Exactly one of `password`, `password_env`, `password_command` and `password_keyring` must
+    /// be given.
+    pub fn from_credentials(creds: &Credentials) -> Result<Self, String> {
+        let mut sources = Vec::with_capacity(1);
+        if let Some(password) = creds.password.as_ref() {
+            sources.push(Self::Literal(password.clone()));
+        }
+        if let Some(env_var) = creds.password_env.as_ref() {
+            sources.push(Self::Environment(env_var.clone()));
+        }
+        if let Some(command) = creds.password_command.as_ref() {
+            sources.push(Self::Command(command.clone()));
+        }
+        if creds.password_keyring {
+            sources.push(Self::Keyring);
+        }
+
+        if sources.len() > 1 {
+            return Err("only one of password, password_env, password_command and password_keyring may be given".to_owned());
+        }
+        sources.pop()
+            .ok_or_else(|| "one of password, password_env, password_command and password_keyring must be given".to_owned())
+    }
+
+    /// Obtains the password for the given bind DN from this source.
+    pub fn obtain(&self, bind_dn: &str) -> Result<String, Error> {
+        match self {
+            Self::Literal(password) => Ok(password.clone()),
+            Self::Environment(env_var) => {
+                std::env::var(env_var)
+                    .map_err(|e| Error::Usage(format!("failed to read password from environment variable {}: {}", env_var, e)))
+            },
+            Self::Command(command) => run_password_command(command),
+            Self::Keyring => obtain_from_keyring(bind_dn),
+            Self::Prompt => {
+                rpassword::prompt_password("LDAP password: ")
+                    .map_err(|error| Error::Io { context: "failed to read password".to_owned(), error })
+            },
+        }
+    }
+}
+
+
+/// Runs a shell command and returns the first line of its output as the password.
+fn run_password_command(command: &str) -> Result<String, Error> {
+    #[cfg(windows)]
+    let (shell, shell_option) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, shell_option) = ("sh", "-c");
+
+    // pass error messages and prompts (e.g. from pinentry) through to the user
+    let output = Command::new(shell)
+        .arg(shell_option)
+        .arg(command)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|error| Error::Io { context: format!("failed to run password command {:?}", command), error })?;
+    if !output.status.success() {
+        return Err(Error::Usage(format!("password command {:?} failed: {}", command, output.status)));
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| Error::Usage(format!("password command {:?} did not output valid UTF-8", command)))?;
+
+    // `pass show` and similar tools output the password on the first line,
+    // optionally followed by more information
+    let password = stdout
+        .split('\n')
+        .next().unwrap_or("")
+        .trim_end_matches('\r');
+    Ok(password.to_owned())
+}
+
+
+#[cfg(feature = "keyring")]
+fn obtain_from_keyring(bind_dn: &str) -> Result<String, Error> {
+    // the service name under which passwords are stored in the keyring
+    const KEYRING_SERVICE: &str = "adldapsearch";
+
+    let bind_dn = bind_dn.to_owned();
+
+    // the Secret Service client starts its own tokio runtime, which must not happen on a thread
+    // that is already running one
+    let result = std::thread::spawn(move || {
+        keyring::Entry::new(KEYRING_SERVICE, &bind_dn)
+            .and_then(|entry| entry.get_password())
+    })
+        .join()
+        .map_err(|_| Error::Usage("keyring lookup panicked".to_owned()))?;
+    result
+        .map_err(|e| Error::Usage(format!("failed to obtain password from keyring: {}", e)))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn obtain_from_keyring(_bind_dn: &str) -> Result<String, Error> {
+    Err(Error::Usage("keyring support has not been enabled; rebuild with `--features keyring`".to_owned()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordSource;
+    use crate::opts::Credentials;
+
+    #[test]
+    fn test_from_credentials() {
+        let creds: Credentials = toml::from_str(r#"
+            bind_dn = "cn=svc,dc=example,dc=com"
+            password_command = "pass show ad/svc"
+        "#).unwrap();
+        assert_eq!(
+            PasswordSource::from_credentials(&creds),
+            Ok(PasswordSource::Command("pass show ad/svc".to_owned())),
+        );
+
+        let creds: Credentials = toml::from_str(r#"
+            bind_dn = "cn=svc,dc=example,dc=com"
+            password = "hunter2"
+            password_keyring = true
+        "#).unwrap();
+        assert!(PasswordSource::from_credentials(&creds).is_err());
+
+        let creds: Credentials = toml::from_str(r#"
+            bind_dn = "cn=svc,dc=example,dc=com"
+        "#).unwrap();
+        assert!(PasswordSource::from_credentials(&creds).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_password_command() {
+        let password = PasswordSource::Command("printf 'hunter2\\nlogin: svc\\n'".to_owned())
+            .obtain("cn=svc,dc=example,dc=com")
+            .unwrap();
+        assert_eq!(password, "hunter2");
+    }
+}
@@ -0,0 +1,184 @@
+use std::net::SocketAddr;
+
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use rand::Rng;
+
+use crate::error::Error;
+
+
+/// A server providing a service, as obtained from a DNS SRV record.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct ServiceTarget {
+    pub priority: u16,
+    pub weight: u16,
+    pub host: String,
+    pub port: u16,
+}
+impl ServiceTarget {
+    /// The LDAP URL with which to connect to this server.
+    pub fn url(&self) -> String {
+        format!("ldap://{}:{}/", self.host, self.port)
+    }
+}
+
+
+/// The DNS name of the SRV records listing the domain controllers (or global catalog servers) of
+/// the given domain.
+pub(crate) fn srv_record_name(domain: &str, global_catalog: bool) -> String {
+    let domain = domain.trim_end_matches('.');
+    if global_catalog {
+        format!("_gc._tcp.{}.", domain)
+    } else {
+        format!("_ldap._tcp.dc._msdcs.{}.", domain)
+    }
+}
+
+
+/// Orders service targets as specified by RFC2782: by ascending priority and, within the same
+/// priority, randomly with the probability of each target being chosen next proportional to its
+/// weight.
+pub(crate) fn order_targets<R: Rng>(mut targets: Vec<ServiceTarget>, rng: &mut R) -> Vec<ServiceTarget> {
+    targets.sort_unstable_by_key(|t| t.priority);
+
+    let mut ordered = Vec::with_capacity(targets.len());
+    let mut remaining = targets.into_iter().peekable();
+    while let Some(first) = remaining.next() {
+        let priority = first.priority;
+        let mut same_priority = vec![first];
+        while let Some(next) = remaining.next_if(|t| t.priority == priority) {
+            same_priority.push(next);
+        }
+
+        // zero-weight targets first so that they have a tiny chance of being chosen
+        same_priority.sort_by_key(|t| t.weight != 0);
+        while !same_priority.is_empty() {
+            let total_weight: u32 = same_priority.iter()
+                .map(|t| u32::from(t.weight))
+                .sum();
+            let chosen_sum = rng.gen_range(0..=total_weight);
+            let mut running_sum = 0;
+            let mut chosen_index = same_priority.len() - 1;
+            for (i, target) in same_priority.iter().enumerate() {
+                running_sum += u32::from(target.weight);
+                if running_sum >= chosen_sum {
+                    chosen_index = i;
+                    break;
+                }
+            }
+            ordered.push(same_priority.remove(chosen_index));
+        }
+    }
+    ordered
+}
+
+
+/// Finds the domain controllers (or global catalog servers) of the given domain using DNS SRV
+/// records and returns them in the order in which they should be tried.
+///
+/// If `dns_server` is given, it is queried instead of the name servers configured on the system.
+pub(crate) async fn discover_domain_controllers(
+    domain: &str,
+    global_catalog: bool,
+    dns_server: Option<SocketAddr>,
+) -> Result<Vec<ServiceTarget>, Error> {
+    let resolver = match dns_server {
+        Some(server) => {
+            let name_servers = NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true);
+            let config = ResolverConfig::from_parts(None, Vec::new(), name_servers);
+            TokioAsyncResolver::tokio(config, ResolverOpts::default())
+        },
+        None => TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| Error::Discovery(format!("failed to set up DNS resolver: {}", e)))?,
+    };
+
+    let record_name = srv_record_name(domain, global_catalog);
+    let lookup = resolver.srv_lookup(record_name.as_str())
+        .await
+        .map_err(|e| Error::Discovery(format!("failed to look up {}: {}", record_name, e)))?;
+    let targets: Vec<ServiceTarget> = lookup.iter()
+        // a target of "." means that the service is decidedly not available
+        .filter(|srv| !srv.target().is_root())
+        .map(|srv| ServiceTarget {
+            priority: srv.priority(),
+            weight: srv.weight(),
+            host: srv.target().to_utf8().trim_end_matches('.').to_owned(),
+            port: srv.port(),
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err(Error::Discovery(format!("no servers listed in {}", record_name)));
+    }
+
+    Ok(order_targets(targets, &mut rand::thread_rng()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::proto::op::{Message, MessageType};
+    use hickory_resolver::proto::rr::{Name, RData, Record};
+    use hickory_resolver::proto::rr::rdata::SRV;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use tokio::net::UdpSocket;
+
+    fn target(priority: u16, weight: u16, host: &str) -> ServiceTarget {
+        ServiceTarget { priority, weight, host: host.to_owned(), port: 389 }
+    }
+
+    #[test]
+    fn test_order_targets() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for _ in 0..32 {
+            let ordered = order_targets(
+                vec![
+                    target(10, 50, "dc3.example.com"),
+                    target(0, 100, "dc1.example.com"),
+                    target(10, 50, "dc4.example.com"),
+                    target(0, 0, "dc2.example.com"),
+                    target(20, 0, "dc5.example.com"),
+                ],
+                &mut rng,
+            );
+            let hosts: Vec<&str> = ordered.iter().map(|t| t.host.as_str()).collect();
+            assert_eq!(hosts.len(), 5);
+            assert!(hosts[0..2].contains(&"dc1.example.com"));
+            assert!(hosts[0..2].contains(&"dc2.example.com"));
+            assert!(hosts[2..4].contains(&"dc3.example.com"));
+            assert!(hosts[2..4].contains(&"dc4.example.com"));
+            assert_eq!(hosts[4], "dc5.example.com");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_domain_controllers() {
+        // a stub DNS server that answers every query with two SRV records
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (len, client) = socket.recv_from(&mut buf).await.unwrap();
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                let query = request.queries()[0].clone();
+                let mut response = Message::new();
+                response.set_id(request.id());
+                response.set_message_type(MessageType::Response);
+                response.set_recursion_desired(request.recursion_desired());
+                response.add_query(query.clone());
+                for (priority, host) in [(0, "dc1.example.com."), (10, "dc2.example.com.")] {
+                    let srv = SRV::new(priority, 100, 389, Name::from_ascii(host).unwrap());
+                    response.add_answer(Record::from_rdata(query.name().clone(), 600, RData::SRV(srv)));
+                }
+                socket.send_to(&response.to_vec().unwrap(), client).await.unwrap();
+            }
+        });
+
+        let targets = discover_domain_controllers("example.com", false, Some(server_addr))
+            .await.unwrap();
+        let urls: Vec<String> = targets.iter().map(|t| t.url()).collect();
+        assert_eq!(urls, ["ldap://dc1.example.com:389/", "ldap://dc2.example.com:389/"]);
+    }
+}
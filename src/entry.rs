@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use adldapsearch::values::LdapValue;
+use ldap3::{Ldap, Scope, SearchEntry};
+use ldap3::controls::RawControl;
+
+use crate::DEFAULT_FILTER;
+use crate::error::Error;
+
+
+/// Collects the string and binary attribute values of a search result entry into one map.
+pub(crate) fn entry_values(entry: SearchEntry) -> BTreeMap<String, Vec<LdapValue>> {
+    let mut all_keys_values: BTreeMap<String, Vec<LdapValue>> = BTreeMap::new();
+    for (str_key, str_values) in entry.attrs {
+        let values = all_keys_values
+            .entry(str_key)
+            .or_insert_with(|| Vec::with_capacity(str_values.len()));
+        for str_value in str_values {
+            values.push(LdapValue::String(str_value));
+        }
+    }
+    for (bin_key, bin_values) in entry.bin_attrs {
+        let values = all_keys_values
+            .entry(bin_key)
+            .or_insert_with(|| Vec::with_capacity(bin_values.len()));
+        for bin_value in bin_values {
+            values.push(LdapValue::Binary(bin_value));
+        }
+    }
+    all_keys_values
+}
+
+
+/// An attribute description with a range option, such as `member;range=0-1499`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct RangedAttribute {
+    /// The attribute description without the range option, e.g. `member`.
+    pub attribute: String,
+
+    /// The index of the first value returned.
+    pub low: u32,
+
+    /// The index of the last value returned, or `None` if the last value of the attribute has
+    /// been returned (`*`).
+    pub high: Option<u32>,
+}
+impl RangedAttribute {
+    /// Parses an attribute description containing a range option. Returns `None` if the attribute
+    /// description does not contain a valid range option.
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut attribute = String::with_capacity(key.len());
+        let mut range = None;
+        for (i, piece) in key.split(';').enumerate() {
+            if i > 0 {
+                if let Some(range_str) = piece.strip_prefix("range=") {
+                    range = Some(range_str);
+                    continue;
+                }
+                attribute.push(';');
+            }
+            attribute.push_str(piece);
+        }
+
+        let (low_str, high_str) = range?.split_once('-')?;
+        let low = low_str.parse().ok()?;
+        let high = if high_str == "*" {
+            None
+        } else {
+            Some(high_str.parse().ok()?)
+        };
+        Some(Self { attribute, low, high })
+    }
+
+    /// Whether this range directly follows a range ending at the given index, i.e. whether it
+    /// starts at the next index and does not end before it starts.
+    pub fn follows(&self, previous_high: u32) -> bool {
+        previous_high.checked_add(1) == Some(self.low)
+            && self.high.is_none_or(|high| high >= self.low)
+    }
+}
+
+
+/// Returns the DN with which to search for the entry with the given DN.
+///
+/// With the extended DN control, the DN is prefixed with the GUID and SID of the object, e.g.
+/// `<GUID=…>;<SID=…>;CN=…`. The GUID component on its own is a valid search base which also finds
+/// the object if it has been moved or deleted in the meantime; otherwise, the prefixes are removed.
+fn search_base(dn: &str) -> &str {
+    let mut rest = dn;
+    while let Some(component_end) = rest.strip_prefix('<').and_then(|r| r.find('>')) {
+        let (component, after) = rest.split_at(component_end + 2);
+        if component.starts_with("<GUID=") {
+            return component;
+        }
+        rest = after.strip_prefix(';').unwrap_or(after);
+    }
+    rest
+}
+
+
+/// Retrieves the remaining values of attributes that have been returned only partially.
+///
+/// Active Directory returns at most `MaxValRange` (by default 1500) values of a multi-valued
+/// attribute at once, e.g. as `member;range=0-1499`. The remaining values must be requested
+/// explicitly (`member;range=1500-*`) until the server responds with an open-ended range
+/// (`member;range=3000-*`). The values are then merged under the attribute name without the range
+/// option.
+///
+/// The given controls are passed with each of these requests; they should be the ones that
+/// determine the visibility of the entry and the format of its values (see
+/// [`entry_controls`](crate::controls::entry_controls)).
+pub(crate) async fn retrieve_ranged_values(
+    ldap: &mut Ldap,
+    dn: &str,
+    controls: &[RawControl],
+    all_keys_values: &mut BTreeMap<String, Vec<LdapValue>>,
+) -> Result<(), Error> {
+    let base = search_base(dn);
+    let ranged_keys: Vec<String> = all_keys_values.keys()
+        .filter(|k| RangedAttribute::parse(k).is_some())
+        .cloned()
+        .collect();
+
+    for ranged_key in ranged_keys {
+        let mut values = all_keys_values.remove(&ranged_key).unwrap();
+        let mut range = RangedAttribute::parse(&ranged_key).unwrap();
+
+        while let Some(high) = range.high {
+            let range_error = |message: String| Error::Parse {
+                context: format!("failed to retrieve the values of {}", range.attribute),
+                message,
+            };
+            let next_low = high.checked_add(1)
+                .ok_or_else(|| range_error("the range ends at the largest possible index".to_owned()))?;
+            let request_key = format!("{};range={}-*", range.attribute, next_low);
+            if !controls.is_empty() {
+                ldap.with_controls(controls.to_vec());
+            }
+            let (results, _response) = ldap.search(
+                base,
+                Scope::Base,
+                DEFAULT_FILTER,
+                &[request_key.as_str()],
+            )
+                .await.and_then(|r| r.success())
+                .map_err(Error::ldap("ranged attribute retrieval"))?;
+
+            let mut next_range = None;
+            for result_entry in results {
+                let entry_values = entry_values(SearchEntry::construct(result_entry));
+                for (key, mut key_values) in entry_values {
+                    if let Some(key_range) = RangedAttribute::parse(&key) {
+                        if key_range.attribute.eq_ignore_ascii_case(&range.attribute) {
+                            values.append(&mut key_values);
+                            next_range = Some(key_range);
+                        }
+                    }
+                }
+            }
+
+            match next_range {
+                Some(nr) => {
+                    // a range that does not continue where the previous one ended would loop forever
+                    if !nr.follows(high) {
+                        return Err(range_error(format!(
+                            "requested values from index {}, received range {}-{}",
+                            next_low, nr.low, nr.high.map_or("*".to_owned(), |nh| nh.to_string()),
+                        )));
+                    }
+                    range = nr;
+                },
+                // the values have vanished in the meantime
+                None => break,
+            }
+        }
+
+        all_keys_values
+            .entry(range.attribute)
+            .or_default()
+            .append(&mut values);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{RangedAttribute, search_base};
+
+    #[test]
+    fn test_parse_ranged_attribute() {
+        assert_eq!(
+            RangedAttribute::parse("member;range=0-1499"),
+            Some(RangedAttribute { attribute: "member".to_owned(), low: 0, high: Some(1499) }),
+        );
+        assert_eq!(
+            RangedAttribute::parse("member;range=1500-*"),
+            Some(RangedAttribute { attribute: "member".to_owned(), low: 1500, high: None }),
+        );
+        assert_eq!(
+            RangedAttribute::parse("userCertificate;binary;range=0-99"),
+            Some(RangedAttribute { attribute: "userCertificate;binary".to_owned(), low: 0, high: Some(99) }),
+        );
+        assert_eq!(RangedAttribute::parse("member"), None);
+        assert_eq!(RangedAttribute::parse("member;range=0"), None);
+        assert_eq!(RangedAttribute::parse("member;range=x-*"), None);
+    }
+
+    #[test]
+    fn test_range_follows() {
+        assert!(RangedAttribute::parse("member;range=1500-2999").unwrap().follows(1499));
+        assert!(RangedAttribute::parse("member;range=1500-*").unwrap().follows(1499));
+        // repeated or backwards ranges
+        assert!(!RangedAttribute::parse("member;range=0-1499").unwrap().follows(1499));
+        assert!(!RangedAttribute::parse("member;range=1500-1400").unwrap().follows(1499));
+        assert!(!RangedAttribute::parse("member;range=0-*").unwrap().follows(u32::MAX));
+    }
+
+    #[test]
+    fn test_search_base() {
+        assert_eq!(search_base("CN=jdoe,DC=example,DC=com"), "CN=jdoe,DC=example,DC=com");
+        assert_eq!(
+            search_base("<GUID=8a0c3bd3ab6b4f4bb7a5b0d5a3a6c9c1>;<SID=010500000000000515000000>;CN=jdoe,DC=example,DC=com"),
+            "<GUID=8a0c3bd3ab6b4f4bb7a5b0d5a3a6c9c1>",
+        );
+        assert_eq!(
+            search_base("<SID=S-1-5-21-1-2-3-1104>;<GUID=8a0c3bd3-ab6b-4f4b-b7a5-b0d5a3a6c9c1>;CN=jdoe,DC=example,DC=com"),
+            "<GUID=8a0c3bd3-ab6b-4f4b-b7a5-b0d5a3a6c9c1>",
+        );
+        // no GUID component
+        assert_eq!(search_base("<SID=S-1-5-21-1-2-3-1104>;CN=jdoe,DC=example,DC=com"), "CN=jdoe,DC=example,DC=com");
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::io;
+
+use ldap3::LdapError;
+
+
+/// Exit code for errors that do not fall into any other category.
+pub(crate) const EXIT_GENERAL: u8 = 1;
+
+/// Exit code for invalid command-line arguments (also used by clap).
+pub(crate) const EXIT_USAGE: u8 = 2;
+
+/// Exit code if no LDAP server could be found or the connection to it could not be established or
+/// was lost.
+pub(crate) const EXIT_CONNECTION: u8 = 3;
+
+/// Exit code if the LDAP server rejected the credentials (LDAP result code 49).
+pub(crate) const EXIT_INVALID_CREDENTIALS: u8 = 4;
+
+/// Exit code if the base DN does not exist (LDAP result code 32).
+pub(crate) const EXIT_NO_SUCH_OBJECT: u8 = 5;
+
+/// Exit code if the search returned more entries than permitted (LDAP result code 4).
+pub(crate) const EXIT_SIZE_LIMIT_EXCEEDED: u8 = 6;
+
+/// Exit code if the search took longer than permitted (LDAP result code 3).
+pub(crate) const EXIT_TIME_LIMIT_EXCEEDED: u8 = 7;
+
+/// Exit code if the bound user may not perform the operation (LDAP result code 50).
+pub(crate) const EXIT_INSUFFICIENT_ACCESS: u8 = 8;
+
+/// Exit code for any other error result returned by the LDAP server.
+pub(crate) const EXIT_LDAP_OTHER: u8 = 9;
+
+/// Exit code if the output or a file to be written could not be serialized.
+pub(crate) const EXIT_SERIALIZE: u8 = 10;
+
+/// Description of the exit codes, shown in the long help.
+pub(crate) const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  other error (e.g. unreadable or invalid input file)
+  2  invalid command-line arguments
+  3  no LDAP server found, connection failed or TLS verification failed
+  4  invalid credentials (LDAP result code 49)
+  5  no such object (LDAP result code 32)
+  6  size limit exceeded (LDAP result code 4)
+  7  time limit exceeded (LDAP result code 3)
+  8  insufficient access rights (LDAP result code 50)
+  9  other LDAP error
+ 10  output or state could not be serialized (e.g. as JSON or TOML)";
+
+
+/// An error that terminates the program.
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// Reading from a file or the terminal failed.
+    Io { context: String, error: io::Error },
+
+    /// The contents of a file or a value given on the command line could not be parsed.
+    Parse { context: String, message: String },
+
+    /// The output or the contents of a file could not be serialized.
+    Serialize { context: String, message: String },
+
+    /// The command-line options cannot be fulfilled.
+    Usage(String),
+
+    /// No LDAP server could be found using DNS.
+    Discovery(String),
+
+    /// The connection to the LDAP server could not be established.
+    Connect(Box<LdapError>),
+
+    /// The TLS connection could not be set up or verified.
+    Tls(String),
+
+    /// An LDAP operation failed.
+    Ldap { operation: &'static str, error: Box<LdapError> },
+
+    /// No base DN was given and none could be found in the root DSE.
+    NoBaseDn,
+}
+impl Error {
+    /// Returns a closure that wraps an [`LdapError`] encountered during the given operation.
+    pub fn ldap(operation: &'static str) -> impl FnOnce(LdapError) -> Self {
+        move |error| Self::Ldap { operation, error: Box::new(error) }
+    }
+
+    /// Wraps an [`LdapError`] encountered while connecting to the LDAP server.
+    pub fn connect(error: LdapError) -> Self {
+        Self::Connect(Box::new(error))
+    }
+
+    /// Whether the error is specific to the server, i.e. whether another server might succeed.
+    pub fn is_server_unavailable(&self) -> bool {
+        match self {
+            Self::Connect(_) | Self::Tls(_) => true,
+            Self::Ldap { error, .. } => match error.as_ref() {
+                // busy, unavailable
+                LdapError::LdapResult { result } => matches!(result.rc, 51 | 52),
+                _ => self.exit_code() == EXIT_CONNECTION,
+            },
+            _ => false,
+        }
+    }
+
+    /// The process exit code corresponding to this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Io { .. } | Self::Parse { .. } | Self::NoBaseDn => EXIT_GENERAL,
+            Self::Usage(_) => EXIT_USAGE,
+            Self::Serialize { .. } => EXIT_SERIALIZE,
+            Self::Discovery(_) | Self::Connect(_) | Self::Tls(_) => EXIT_CONNECTION,
+            Self::Ldap { error, .. } => match error.as_ref() {
+                LdapError::LdapResult { result } => match result.rc {
+                    3 => EXIT_TIME_LIMIT_EXCEEDED,
+                    4 => EXIT_SIZE_LIMIT_EXCEEDED,
+                    32 => EXIT_NO_SUCH_OBJECT,
+                    49 => EXIT_INVALID_CREDENTIALS,
+                    50 => EXIT_INSUFFICIENT_ACCESS,
+                    _ => EXIT_LDAP_OTHER,
+                },
+                LdapError::Io { .. }
+                    | LdapError::OpSend { .. }
+                    | LdapError::ResultRecv { .. }
+                    | LdapError::Timeout { .. }
+                    | LdapError::EndOfStream
+                    => EXIT_CONNECTION,
+                _ => EXIT_GENERAL,
+            },
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { context, error }
+                => write!(f, "{}: {}", context, error),
+            Self::Parse { context, message }
+                => write!(f, "{}: {}", context, message),
+            Self::Serialize { context, message }
+                => write!(f, "{}: {}", context, message),
+            Self::Usage(message)
+                => write!(f, "{}", message),
+            Self::Discovery(message)
+                => write!(f, "failed to find LDAP server: {}", message),
+            Self::Connect(error)
+                => write!(f, "failed to connect to LDAP server: {}", error),
+            Self::Tls(message)
+                => write!(f, "TLS error: {}", message),
+            Self::Ldap { operation, error } => match error.as_ref() {
+                LdapError::LdapResult { result } => {
+                    let description = match result.rc {
+                        3 => "time limit exceeded",
+                        4 => "size limit exceeded",
+                        32 => "no such object",
+                        49 => "invalid credentials",
+                        50 => "insufficient access rights",
+                        _ => "LDAP error",
+                    };
+                    write!(f, "{} failed: {} (result code {})", operation, description, result.rc)?;
+                    if !result.matched.is_empty() {
+                        write!(f, "; matched DN: {}", result.matched)?;
+                    }
+                    if !result.text.is_empty() {
+                        write!(f, "; server message: {}", result.text.trim_end_matches('\0').trim_end())?;
+                    }
+                    Ok(())
+                },
+                other => write!(f, "{} failed: {}", operation, other),
+            },
+            Self::NoBaseDn
+                => write!(f, "failed to find base DN from rootDSE; please specify -b/--base-dn"),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            Self::Connect(error) => Some(error.as_ref()),
+            Self::Ldap { error, .. } => Some(error.as_ref()),
+            Self::Parse { .. } | Self::Serialize { .. } | Self::Usage(_) | Self::Discovery(_) | Self::Tls(_) | Self::NoBaseDn => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ldap3::LdapResult;
+
+    fn ldap_error(rc: u32, text: &str) -> Error {
+        let result = LdapResult {
+            rc,
+            matched: String::new(),
+            text: text.to_owned(),
+            refs: Vec::new(),
+            ctrls: Vec::new(),
+        };
+        Error::ldap("bind")(LdapError::LdapResult { result })
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(ldap_error(49, "").exit_code(), EXIT_INVALID_CREDENTIALS);
+        assert_eq!(ldap_error(32, "").exit_code(), EXIT_NO_SUCH_OBJECT);
+        assert_eq!(ldap_error(4, "").exit_code(), EXIT_SIZE_LIMIT_EXCEEDED);
+        assert_eq!(ldap_error(3, "").exit_code(), EXIT_TIME_LIMIT_EXCEEDED);
+        assert_eq!(ldap_error(50, "").exit_code(), EXIT_INSUFFICIENT_ACCESS);
+        assert_eq!(ldap_error(53, "").exit_code(), EXIT_LDAP_OTHER);
+        assert_eq!(Error::NoBaseDn.exit_code(), EXIT_GENERAL);
+        assert_eq!(Error::Usage(String::new()).exit_code(), EXIT_USAGE);
+        assert_eq!(Error::Serialize { context: String::new(), message: String::new() }.exit_code(), EXIT_SERIALIZE);
+    }
+
+    #[test]
+    fn test_display() {
+        let error = ldap_error(
+            49,
+            "80090308: LdapErr: DSID-0C090569, comment: AcceptSecurityContext error, data 52e, v4563\0",
+        );
+        assert_eq!(
+            error.to_string(),
+            "bind failed: invalid credentials (result code 49); server message: 80090308: LdapErr: DSID-0C090569, comment: AcceptSecurityContext error, data 52e, v4563",
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use adldapsearch::values::LdapValue;
+use adldapsearch::values::structs::security::explain::{GuidKind, GuidName, GuidNames};
+use ldap3::{Ldap, Scope, SearchEntry};
+use ldap3::adapters::{Adapter, EntriesOnly, PagedResults};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::DEFAULT_FILTER;
+use crate::entry::entry_values;
+use crate::error::Error;
+
+
+/// The number of entries requested per page when reading the schema and the extended rights.
+const PAGE_SIZE: i32 = 500;
+
+
+/// The names of the schema objects and extended rights of a forest, stored between runs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct GuidCache {
+    /// The DN of the schema naming context from which the names have been read.
+    schema_dn: String,
+
+    /// The `modifyTimeStamp` of the schema naming context when the names have been read. It changes
+    /// with every modification of the schema, as each one updates the `schemaInfo` attribute.
+    #[serde(default)]
+    schema_modified: String,
+
+    names: GuidNames,
+}
+impl GuidCache {
+    /// Loads the cache from the given file, returning `None` if it does not exist.
+    fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let cache_string = match std::fs::read_to_string(path) {
+            Ok(cs) => cs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Io {
+                context: format!("failed to read GUID cache file {}", path.display()),
+                error,
+            }),
+        };
+        toml::from_str(&cache_string)
+            .map(Some)
+            .map_err(|e| Error::Parse {
+                context: format!("failed to parse GUID cache file {}", path.display()),
+                message: e.to_string(),
+            })
+    }
+
+    /// Stores the cache in the given file, creating its directory if necessary.
+    fn save(&self, path: &Path) -> Result<(), Error> {
+        let cache_string = toml::to_string(self)
+            .map_err(|e| Error::Serialize {
+                context: format!("failed to serialize GUID cache for {}", path.display()),
+                message: e.to_string(),
+            })?;
+        let parent_result = match path.parent() {
+            Some(parent) => std::fs::create_dir_all(parent),
+            None => Ok(()),
+        };
+        parent_result
+            .and_then(|()| std::fs::write(path, cache_string))
+            .map_err(|error| Error::Io {
+                context: format!("failed to write GUID cache file {}", path.display()),
+                error,
+            })
+    }
+}
+
+
+/// Returns the names from the cache file if it has been written for the given state of the schema.
+///
+/// A cache never prevents the names from being read from the directory: a cache file that cannot
+/// be read is reported as a warning, and one that cannot be parsed (e.g. because it has been
+/// written by an older version) is ignored.
+fn cached_guid_names(path: &Path, schema_dn: &str, schema_modified: &str) -> Option<GuidNames> {
+    match GuidCache::load(path) {
+        Ok(Some(cache)) => {
+            if cache.schema_dn == schema_dn && cache.schema_modified == schema_modified {
+                Some(cache.names)
+            } else {
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(Error::Parse { .. }) => None,
+        Err(e) => {
+            eprintln!("adldapsearch: {}; reading the GUID names from the directory", e);
+            None
+        },
+    }
+}
+
+
+/// The file name of the GUID cache of the forest with the given schema naming context.
+fn cache_file_name(schema_dn: &str) -> String {
+    let sanitized: String = schema_dn.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    format!("guids-{}.toml", sanitized)
+}
+
+
+/// The default location of the GUID cache of the forest with the given schema naming context.
+fn default_cache_path(schema_dn: &str) -> Option<PathBuf> {
+    let mut path = dirs::cache_dir()?;
+    path.push("adldapsearch");
+    path.push(cache_file_name(schema_dn));
+    Some(path)
+}
+
+
+fn first_value<'a>(values: &'a BTreeMap<String, Vec<LdapValue>>, key: &str) -> Option<&'a [u8]> {
+    let (_key, key_values) = values.iter()
+        .find(|(k, _v)| k.eq_ignore_ascii_case(key))?;
+    match key_values.first()? {
+        LdapValue::String(s) => Some(s.as_bytes()),
+        LdapValue::Binary(b) => Some(b.as_slice()),
+    }
+}
+
+
+fn first_string<'a>(values: &'a BTreeMap<String, Vec<LdapValue>>, key: &str) -> Option<&'a str> {
+    std::str::from_utf8(first_value(values, key)?).ok()
+}
+
+
+async fn search_all(
+    ldap: &mut Ldap,
+    base_dn: &str,
+    scope: Scope,
+    filter: &str,
+    attributes: &[&str],
+) -> Result<Vec<BTreeMap<String, Vec<LdapValue>>>, Error> {
+    let adapters: Vec<Box<dyn Adapter<_, _>>> = vec![
+        Box::new(EntriesOnly::new()),
+        Box::new(PagedResults::new(PAGE_SIZE)),
+    ];
+    let mut search_stream = ldap.streaming_search_with(adapters, base_dn, scope, filter, attributes)
+        .await.map_err(Error::ldap("GUID name search"))?;
+    let mut entries = Vec::new();
+    while let Some(result_entry) = search_stream.next().await.map_err(Error::ldap("GUID name search"))? {
+        entries.push(entry_values(SearchEntry::construct(result_entry)));
+    }
+    search_stream.finish().await
+        .success().map_err(Error::ldap("GUID name search"))?;
+    Ok(entries)
+}
+
+
+/// Reads the names of all schema objects and extended rights from the directory.
+async fn fetch_guid_names(ldap: &mut Ldap, schema_dn: &str, configuration_dn: &str) -> Result<GuidNames, Error> {
+    let mut names = GuidNames::new();
+
+    let schema_objects = search_all(
+        ldap,
+        schema_dn,
+        Scope::OneLevel,
+        "(|(objectClass=attributeSchema)(objectClass=classSchema))",
+        &["lDAPDisplayName", "schemaIDGUID", "objectClass"],
+    ).await?;
+    for schema_object in schema_objects {
+        let Some(name) = first_string(&schema_object, "lDAPDisplayName") else { continue };
+        let Some(guid) = first_value(&schema_object, "schemaIDGUID").and_then(|g| Uuid::from_slice_le(g).ok()) else { continue };
+        let is_class = schema_object.get("objectClass")
+            .map(|ocs| ocs.iter().any(|oc| oc.is_string("classSchema")))
+            .unwrap_or(false);
+        let kind = if is_class { GuidKind::Class } else { GuidKind::Attribute };
+        names.insert(guid, GuidName { kind, name: name.to_owned() });
+    }
+
+    let extended_rights_dn = format!("CN=Extended-Rights,{}", configuration_dn);
+    let extended_rights = search_all(
+        ldap,
+        &extended_rights_dn,
+        Scope::OneLevel,
+        "(objectClass=controlAccessRight)",
+        &["displayName", "rightsGuid", "validAccesses"],
+    ).await?;
+    for extended_right in extended_rights {
+        let Some(name) = first_string(&extended_right, "displayName") else { continue };
+        let Some(guid) = first_string(&extended_right, "rightsGuid").and_then(|g| g.parse().ok()) else { continue };
+        let Some(kind) = first_string(&extended_right, "validAccesses")
+            .and_then(|va| va.parse().ok())
+            .and_then(GuidKind::from_valid_accesses)
+            else { continue };
+        names.insert(guid, GuidName { kind, name: name.to_owned() });
+    }
+
+    Ok(names)
+}
+
+
+/// Reads the time at which the schema has last been modified.
+async fn schema_modified(ldap: &mut Ldap, schema_dn: &str) -> Result<String, Error> {
+    let (schema_head, _response) = ldap.search(schema_dn, Scope::Base, DEFAULT_FILTER, &["modifyTimeStamp"])
+        .await.and_then(|r| r.success())
+        .map_err(Error::ldap("search for schema modification time"))?;
+    let schema_head_values = schema_head.into_iter()
+        .next()
+        .map(|re| entry_values(SearchEntry::construct(re)))
+        .unwrap_or_default();
+    first_string(&schema_head_values, "modifyTimeStamp")
+        .map(|smt| smt.to_owned())
+        .ok_or_else(|| Error::Parse {
+            context: format!("failed to read the schema naming context {}", schema_dn),
+            message: "modifyTimeStamp is missing".to_owned(),
+        })
+}
+
+
+/// Obtains the names of the GUIDs used in object ACEs, from the cache file if it exists and the
+/// schema has not been modified since it has been written, or from the directory otherwise.
+///
+/// If no cache file is given, a file within the user's cache directory is used, named after the
+/// schema naming context.
+pub(crate) async fn load_guid_names(ldap: &mut Ldap, cache_path: Option<&Path>) -> Result<GuidNames, Error> {
+    let (root_dse, _response) = ldap.search(
+        "",
+        Scope::Base,
+        DEFAULT_FILTER,
+        &["schemaNamingContext", "configurationNamingContext"],
+    )
+        .await.and_then(|r| r.success())
+        .map_err(Error::ldap("search for rootDSE"))?;
+    let root_dse_values = root_dse.into_iter()
+        .next()
+        .map(|re| entry_values(SearchEntry::construct(re)))
+        .unwrap_or_default();
+    let (Some(schema_dn), Some(configuration_dn)) = (
+        first_string(&root_dse_values, "schemaNamingContext"),
+        first_string(&root_dse_values, "configurationNamingContext"),
+    ) else {
+        return Err(Error::Usage("the server does not provide the schema and configuration naming contexts required by --explain".to_owned()));
+    };
+
+    let cache_path = match cache_path {
+        Some(cp) => Some(cp.to_owned()),
+        None => default_cache_path(schema_dn),
+    };
+    let schema_modified = schema_modified(ldap, schema_dn).await?;
+    if let Some(cp) = cache_path.as_ref() {
+        if let Some(names) = cached_guid_names(cp, schema_dn, &schema_modified) {
+            return Ok(names);
+        }
+    }
+
+    let names = fetch_guid_names(ldap, schema_dn, configuration_dn).await?;
+    if let Some(cp) = cache_path.as_ref() {
+        let cache = GuidCache {
+            schema_dn: schema_dn.to_owned(),
+            schema_modified,
+            names,
+        };
+        // the names are still usable if they cannot be cached
+        if let Err(e) = cache.save(cp) {
+            eprintln!("adldapsearch: {}", e);
+        }
+        Ok(cache.names)
+    } else {
+        Ok(names)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use adldapsearch::values::structs::security::explain::{GuidKind, GuidName, GuidNames};
+
+    use super::{cache_file_name, cached_guid_names, GuidCache};
+
+    #[test]
+    fn test_cache_file_name() {
+        assert_eq!(
+            cache_file_name("CN=Schema,CN=Configuration,DC=example,DC=com"),
+            "guids-cn_schema_cn_configuration_dc_example_dc_com.toml",
+        );
+        assert_eq!(cache_file_name("CN=Schema,DC=my-forest"), "guids-cn_schema_dc_my-forest.toml");
+    }
+
+    #[test]
+    fn test_cached_guid_names() {
+        let schema_dn = "CN=Schema,CN=Configuration,DC=example,DC=com";
+        let path = std::env::temp_dir()
+            .join(format!("adldapsearch-test-guids-{}.toml", std::process::id()));
+
+        let mut names = GuidNames::new();
+        names.insert(
+            "bf967a7f-0de6-11d0-a285-00aa003049e2".parse().unwrap(),
+            GuidName { kind: GuidKind::Attribute, name: "member".to_owned() },
+        );
+        let cache = GuidCache {
+            schema_dn: schema_dn.to_owned(),
+            schema_modified: "20240101000000.0Z".to_owned(),
+            names: names.clone(),
+        };
+        cache.save(&path).unwrap();
+        assert_eq!(cached_guid_names(&path, schema_dn, "20240101000000.0Z"), Some(names));
+        assert_eq!(cached_guid_names(&path, schema_dn, "20250101000000.0Z"), None);
+
+        // corrupt or outdated cache files are ignored
+        std::fs::write(&path, "names = 42").unwrap();
+        assert_eq!(cached_guid_names(&path, schema_dn, "20240101000000.0Z"), None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cached_guid_names(&path, schema_dn, "20240101000000.0Z"), None);
+
+        // the cache cannot be stored below a file
+        let unwritable = std::env::current_exe().unwrap().join("guids.toml");
+        assert!(cache.save(&unwritable).is_err());
+        assert_eq!(cached_guid_names(&unwritable, schema_dn, "20240101000000.0Z"), None);
+    }
+}
@@ -0,0 +1,9 @@
+//! Decoders for attribute values specific to Active Directory.
+//!
+//! The binary `adldapsearch` queries an LDAP directory and uses these decoders to render the
+//! results; the modules are also exported here so that other tools can reuse them.
+
+
+mod macros;
+pub mod oid_prefix;
+pub mod values;
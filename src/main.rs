@@ -1,25 +1,50 @@
-mod macros;
-mod oid_prefix;
+mod ber;
+mod bind;
+mod config;
+mod connect;
+mod controls;
+mod credentials;
+mod discovery;
+mod entry;
+mod error;
+mod guids;
 mod opts;
-mod values;
+mod output;
+mod sids;
+mod sync;
+mod tls;
+mod watch;
 
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::ExitCode;
 
-use clap::Parser;
-use ldap3::{Ldap, LdapConnAsync, Scope, SearchEntry};
+use base64::prelude::{BASE64_STANDARD, Engine};
+use clap::{CommandFactory, FromArgMatches};
+use ldap3::{Ldap, Scope, SearchEntry};
 use ldap3::adapters::{Adapter, PagedResults};
-use rpassword;
 
-use crate::opts::{Credentials, Opts};
-use crate::values::{LdapValue, output_values};
+use adldapsearch::values::{LdapValue, output_value, TextOutput};
+use adldapsearch::values::json::value_to_json;
+use adldapsearch::values::ldif::{parse_ldif, value_to_ldif};
 
+use crate::config::load_profile;
+use crate::connect::connect;
+use crate::controls::{check_asq_response, entry_controls, report_response_controls, request_controls};
+use crate::entry::{entry_values, retrieve_ranged_values};
+use crate::error::Error;
+use crate::guids::load_guid_names;
+use crate::opts::{Command, DecodeLdifOpts, DecodeOpts, LdapScope, Opts, OutputFormat};
+use crate::output::EntryWriter;
+use crate::sync::sync;
+use crate::watch::watch;
 
-const DEFAULT_FILTER: &str = "(objectClass=*)";
 
+pub(crate) const DEFAULT_FILTER: &str = "(objectClass=*)";
 
-async fn find_base_dn(ldap: &mut Ldap) -> String {
+
+pub(crate) async fn find_base_dn(ldap: &mut Ldap) -> Result<String, Error> {
     // query rootDSE
     const NO_ATTRS: [&str; 0] = [];
     let (results, _response) = ldap.search(
@@ -28,8 +53,8 @@ async fn find_base_dn(ldap: &mut Ldap) -> String {
         DEFAULT_FILTER,
         &NO_ATTRS,
     )
-        .await.expect("failed to search for rootDSE")
-        .success().expect("error while searching for rootDSE");
+        .await.and_then(|r| r.success())
+        .map_err(Error::ldap("search for rootDSE"))?;
     for result_entry in results {
         let entry = SearchEntry::construct(result_entry);
 
@@ -37,80 +62,194 @@ async fn find_base_dn(ldap: &mut Ldap) -> String {
         if let Some(dncs) = entry.attrs.get("defaultNamingContext") {
             // there should only be one value for defaultNamingContext, but y'know
             for dnc in dncs {
-                return dnc.clone();
+                return Ok(dnc.clone());
             }
         }
         if let Some(ncs) = entry.attrs.get("namingContexts") {
             for nc in ncs {
-                return nc.clone();
+                return Ok(nc.clone());
             }
         }
     }
-    panic!("failed to find base DN from rootDSE; please specify -b/--base-dn");
+    Err(Error::NoBaseDn)
 }
 
 
-async fn run() {
-    let o = Opts::parse();
+fn decode_ldif(opts: &DecodeLdifOpts) -> Result<(), Error> {
+    let ldif_string = if opts.file.as_os_str() == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf)
+            .map_err(|error| Error::Io { context: "failed to read LDIF from standard input".to_owned(), error })?;
+        buf
+    } else {
+        std::fs::read_to_string(&opts.file)
+            .map_err(|error| Error::Io {
+                context: format!("failed to read LDIF file {}", opts.file.display()),
+                error,
+            })?
+    };
+    let records = parse_ldif(&ldif_string)
+        .map_err(|e| Error::Parse {
+            context: format!("failed to parse LDIF file {}", opts.file.display()),
+            message: e.to_string(),
+        })?;
 
-    let (bind_dn, password) = if let Some(credentials_file) = o.credentials_file.as_ref() {
-        let creds_file_string = match std::fs::read_to_string(credentials_file) {
-            Ok(cfs) => cfs,
-            Err(e) => panic!("failed to read credentials file {}: {}", credentials_file.display(), e),
-        };
-        let creds: Credentials = match toml::from_str(&creds_file_string) {
-            Ok(c) => c,
-            Err(e) => panic!("failed to parse credentials file {}: {}", credentials_file.display(), e),
-        };
-        if let Some(bind_dn) = o.bind_dn {
-            (bind_dn, creds.password)
-        } else {
-            (creds.bind_dn, creds.password)
+    let mut writer = EntryWriter::new(&opts.output);
+    writer.begin();
+    for record in &records {
+        if let Err(e) = writer.write_entry(&record.dn, &record.attributes) {
+            writer.end();
+            return Err(e);
         }
-    } else if let Some(bind_dn) = o.bind_dn {
-        let password = rpassword::prompt_password("LDAP password: ")
-            .expect("failed to read password");
-        (bind_dn, password)
+    }
+    writer.end();
+    Ok(())
+}
+
+
+pub(crate) fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = hex.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|d| u8::try_from(d).unwrap()))
+        .collect::<Option<Vec<u8>>>()?;
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        // odd number of digits
+        return None;
+    }
+    let bytes = pairs
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect();
+    Some(bytes)
+}
+
+
+fn decode(opts: &DecodeOpts) -> Result<(), Error> {
+    let value_bytes = if let Some(hex) = opts.hex.as_ref() {
+        hex_to_bytes(hex)
+            .ok_or_else(|| Error::Parse {
+                context: "failed to decode value as hexadecimal".to_owned(),
+                message: "invalid digit or odd number of digits".to_owned(),
+            })?
+    } else if let Some(base64) = opts.base64.as_ref() {
+        BASE64_STANDARD.decode(base64.trim())
+            .map_err(|e| Error::Parse {
+                context: "failed to decode value as base64".to_owned(),
+                message: e.to_string(),
+            })?
+    } else if let Some(raw) = opts.raw.as_ref() {
+        raw.as_bytes().to_vec()
     } else {
-        panic!("at least one of -D/--bind-dn or -c/--credentials-file must be given");
+        // enforced by clap
+        unreachable!()
+    };
+
+    // the server returns values as strings if they are valid UTF-8; do the same here
+    let value = match String::from_utf8(value_bytes) {
+        Ok(s) => LdapValue::String(s),
+        Err(e) => LdapValue::Binary(e.into_bytes()),
+    };
+    let object_classes: Vec<LdapValue> = opts.object_classes.iter()
+        .map(|oc| LdapValue::String(oc.clone()))
+        .collect();
+
+    match opts.output.format {
+        OutputFormat::Text => {
+            output_value(&mut TextOutput::default(), &opts.attribute, &value, &object_classes);
+        },
+        OutputFormat::Json => {
+            let json_string = value_to_json(&opts.attribute, &value, &object_classes, None)
+                .and_then(|json_value| serde_json::to_string_pretty(&json_value))
+                .map_err(|e| Error::Serialize {
+                    context: "failed to serialize value as JSON".to_owned(),
+                    message: e.to_string(),
+                })?;
+            println!("{}", json_string);
+        },
+        OutputFormat::JsonLines => {
+            let json_string = value_to_json(&opts.attribute, &value, &object_classes, None)
+                .and_then(|json_value| serde_json::to_string(&json_value))
+                .map_err(|e| Error::Serialize {
+                    context: "failed to serialize value as JSON".to_owned(),
+                    message: e.to_string(),
+                })?;
+            println!("{}", json_string);
+        },
+        OutputFormat::Ldif => {
+            print!("{}", value_to_ldif(&opts.attribute, &value, &object_classes, !opts.output.ldif_no_comments, None));
+        },
+    }
+    Ok(())
+}
+
+
+async fn run() -> Result<(), Error> {
+    let matches = Opts::command().get_matches();
+    let mut o = Opts::from_arg_matches(&matches)
+        .unwrap_or_else(|e| e.exit());
+
+    match o.command.take() {
+        Some(Command::DecodeLdif(decode_ldif_opts)) => decode_ldif(&decode_ldif_opts),
+        Some(Command::Decode(decode_opts)) => decode(&decode_opts),
+        Some(Command::Sync(mut sync_opts)) => {
+            if let Some(profile_name) = sync_opts.connection.profile.as_ref() {
+                let profile = load_profile(profile_name)?;
+                let sync_matches = matches.subcommand_matches("sync").unwrap();
+                profile.apply_to_sync(&mut sync_opts, sync_matches)?;
+            }
+            sync(*sync_opts).await
+        },
+        Some(Command::Watch(mut watch_opts)) => {
+            if let Some(profile_name) = watch_opts.connection.profile.as_ref() {
+                let profile = load_profile(profile_name)?;
+                let watch_matches = matches.subcommand_matches("watch").unwrap();
+                profile.apply_to_watch(&mut watch_opts, watch_matches)?;
+            }
+            watch(*watch_opts).await
+        },
+        None => {
+            if let Some(profile_name) = o.connection.profile.as_ref() {
+                let profile = load_profile(profile_name)?;
+                profile.apply_to(&mut o, &matches)?;
+            }
+            search(o).await
+        },
+    }
+}
+
+
+async fn search(o: Opts) -> Result<(), Error> {
+    let scope = match (o.scope, o.asq.is_some()) {
+        (Some(LdapScope::Base), _) | (None, true) => LdapScope::Base,
+        (Some(_), true) => return Err(Error::Usage("--asq requires -s/--scope base".to_owned())),
+        (Some(s), false) => s,
+        (None, false) => return Err(Error::Usage("-s/--scope must be given, either directly or through --profile".to_owned())),
     };
 
-    // connect to LDAP server
-    let (conn, mut ldap) = LdapConnAsync::new(&o.url)
-        .await.expect("failed to connect to LDAP server");
-    ldap3::drive!(conn);
+    let mut ldap = connect(&o.connection).await?;
 
     let filter = o.filter.as_deref()
         .unwrap_or(DEFAULT_FILTER);
 
-    ldap.simple_bind(&bind_dn, &password)
-        .await.expect("failed to bind to LDAP server");
-
     let base_dn = match o.base_dn.as_deref() {
         Some(bdn) => Cow::Borrowed(bdn),
-        None => Cow::Owned(find_base_dn(&mut ldap).await),
+        None => Cow::Owned(find_base_dn(&mut ldap).await?),
     };
 
-    if o.avoid_sacl {
-        const LDAP_SERVER_SD_FLAGS_OID: &str = "1.2.840.113556.1.4.801";
-        // payload is a BER-encoded SEQUENCE { INTEGER }
-        // where the INTEGER is a combination of these bitflags:
-        // 0x1 = owner, 0x2 = group, 0x4 = DACL, 0x8 = SACL
-        // with an unprivileged user, we can generally read owner, group and DACL and not SACL
-        // (if we try, the relevant attribute simply isn't returned)
-        const PAYLOAD: [u8; 5] = [
-            0x30, // ASN.1 SEQUENCE
-            0x03, // 3 bytes long
-                0x02, // ASN.1 INTEGER
-                0x01, // 1 byte long
-                0x07, // owner | group | DACL
-        ];
-        let avoid_sacl_control = ldap3::controls::RawControl {
-            ctype: LDAP_SERVER_SD_FLAGS_OID.to_owned(),
-            crit: false,
-            val: Some(PAYLOAD.to_vec()),
-        };
-        ldap.with_controls(avoid_sacl_control);
+    // the GUID names are read before the controls are set, as these only apply to the next search
+    let mut writer = EntryWriter::new(&o.output);
+    if o.explain {
+        let guid_names = load_guid_names(&mut ldap, o.guid_cache.as_deref()).await?;
+        writer.set_guid_names(guid_names);
+    }
+    if o.resolve_sids {
+        writer.enable_sid_names();
+    }
+
+    let ranged_retrieval_controls = entry_controls(&o);
+    let controls = request_controls(&o);
+    if !controls.is_empty() {
+        ldap.with_controls(controls);
     }
 
     let mut adapters: Vec<Box<dyn Adapter<_, _>>> = Vec::with_capacity(1);
@@ -122,16 +261,20 @@ async fn run() {
     let mut search_stream = ldap.streaming_search_with(
         adapters,
         &base_dn,
-        o.scope.into(),
+        scope.into(),
         filter,
         o.attributes.as_slice(),
     )
-        .await.expect("search failed");
+        .await.map_err(Error::ldap("search"))?;
+    writer.begin();
     loop {
         let result_entry = match search_stream.next().await {
             Ok(Some(re)) => re,
             Ok(None) => break,
-            Err(e) => panic!("obtaining search results failed: {}", e),
+            Err(e) => {
+                writer.end();
+                return Err(Error::ldap("search")(e));
+            },
         };
         if result_entry.is_ref() {
             // I care not for such trifles
@@ -139,40 +282,44 @@ async fn run() {
         }
 
         let entry = SearchEntry::construct(result_entry);
-        println!();
-        println!("dn: {}", entry.dn);
-
-        let mut all_keys_values: BTreeMap<String, Vec<LdapValue>> = BTreeMap::new();
-        for (str_key, str_values) in entry.attrs {
-            let values = all_keys_values
-                .entry(str_key)
-                .or_insert_with(|| Vec::with_capacity(str_values.len()));
-            for str_value in str_values {
-                values.push(LdapValue::String(str_value));
-            }
+        let dn = entry.dn.clone();
+        let mut all_keys_values = entry_values(entry);
+        if let Err(e) = retrieve_ranged_values(&mut ldap, &dn, &ranged_retrieval_controls, &mut all_keys_values).await {
+            writer.end();
+            return Err(e);
         }
-        for (bin_key, bin_values) in entry.bin_attrs {
-            let values = all_keys_values
-                .entry(bin_key)
-                .or_insert_with(|| Vec::with_capacity(bin_values.len()));
-            for bin_value in bin_values {
-                values.push(LdapValue::Binary(bin_value));
+        if let Some(sid_cache) = writer.sid_cache_mut() {
+            if let Err(e) = sid_cache.resolve(&ldap, &all_keys_values).await {
+                writer.end();
+                return Err(e);
             }
         }
 
-        let object_classes = all_keys_values
-            .get("objectClass")
-            .map(|oc| oc.as_slice())
-            .unwrap_or(&[]);
-
-        for (key, values) in &all_keys_values {
-            output_values(key, values, object_classes);
+        if let Err(e) = writer.write_entry(&dn, &all_keys_values) {
+            writer.end();
+            return Err(e);
         }
     }
+    writer.end();
+
+    // e.g. size limit exceeded is only reported at the end of the search
+    let search_result = search_stream.finish().await;
+    report_response_controls(&search_result.ctrls);
+    let response_controls = search_result.ctrls.clone();
+    search_result
+        .success().map_err(Error::ldap("search"))?;
+    check_asq_response(&response_controls)?;
+    Ok(())
 }
 
 
 #[tokio::main]
-async fn main() {
-    run().await
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("adldapsearch: {}", e);
+            ExitCode::from(e.exit_code())
+        },
+    }
 }
@@ -3,6 +3,7 @@ use std::fmt;
 use std::ops::Range;
 
 use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
 
 use crate::{bit_is_set, extract_bits_noconvert};
 
@@ -135,6 +136,11 @@ impl fmt::Display for OidPrefix {
         write!(f, "({}..{})", self.final_range.start, self.final_range.end)
     }
 }
+impl Serialize for OidPrefix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
 
 #[cfg(test)]
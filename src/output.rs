@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+use serde_json::Value;
+
+use adldapsearch::values::{LdapValue, output_values, SECURITY_DESCRIPTOR_ATTRIBUTES};
+use adldapsearch::values::json::entry_to_json;
+use adldapsearch::values::ldif::entry_to_ldif;
+use adldapsearch::values::structs::security::{SecurityDescriptor, SidNames};
+use adldapsearch::values::structs::security::explain::GuidNames;
+
+use crate::error::Error;
+use crate::opts::{OutputFormat, OutputOpts};
+use crate::sids::SidCache;
+
+
+fn json_error(e: serde_json::Error) -> Error {
+    Error::Serialize {
+        context: "failed to serialize entry as JSON".to_owned(),
+        message: e.to_string(),
+    }
+}
+
+
+/// Converts an entry into JSON, adding the time at which it has been received if known, the
+/// explanations of its security descriptors if any and the account names of its SIDs if resolved.
+fn entry_to_json_extended(
+    dn: &str,
+    all_keys_values: &BTreeMap<String, Vec<LdapValue>>,
+    received: Option<&str>,
+    explanations: &BTreeMap<&str, Vec<Vec<String>>>,
+    sid_names: Option<&SidNames>,
+) -> Result<Value, Error> {
+    let mut json_entry = entry_to_json(dn, all_keys_values, sid_names)
+        .map_err(json_error)?;
+    if let Value::Object(entry_object) = &mut json_entry {
+        if let Some(received) = received {
+            entry_object.insert("received".to_owned(), Value::String(received.to_owned()));
+        }
+        if !explanations.is_empty() {
+            let explanations_value = serde_json::to_value(explanations)
+                .map_err(json_error)?;
+            entry_object.insert("explained".to_owned(), explanations_value);
+        }
+    }
+    Ok(json_entry)
+}
+
+
+/// Explains the values of the security descriptor attributes of an entry, by attribute.
+///
+/// Values that cannot be decoded as security descriptors are skipped.
+fn explain_security_descriptors<'a>(
+    all_keys_values: &'a BTreeMap<String, Vec<LdapValue>>,
+    guid_names: &GuidNames,
+    sid_names: &SidNames,
+) -> BTreeMap<&'a str, Vec<Vec<String>>> {
+    let mut explanations = BTreeMap::new();
+    for (key, values) in all_keys_values {
+        if !SECURITY_DESCRIPTOR_ATTRIBUTES.contains(&key.as_str()) {
+            continue;
+        }
+        let key_explanations: Vec<Vec<String>> = values.iter()
+            .filter_map(|value| {
+                let value_bytes = match value {
+                    LdapValue::String(s) => s.as_bytes(),
+                    LdapValue::Binary(b) => b.as_slice(),
+                };
+                SecurityDescriptor::try_from_bytes(value_bytes)
+            })
+            .map(|sd| sd.explain(guid_names, sid_names))
+            .collect();
+        if !key_explanations.is_empty() {
+            explanations.insert(key.as_str(), key_explanations);
+        }
+    }
+    explanations
+}
+
+
+/// Outputs directory entries in the format selected by the user.
+pub(crate) struct EntryWriter {
+    format: OutputFormat,
+    ldif_comments: bool,
+    is_first_entry: bool,
+    guid_names: Option<GuidNames>,
+    sid_cache: Option<SidCache>,
+}
+impl EntryWriter {
+    pub fn new(output_opts: &OutputOpts) -> Self {
+        Self {
+            format: output_opts.format,
+            ldif_comments: !output_opts.ldif_no_comments,
+            is_first_entry: true,
+            guid_names: None,
+            sid_cache: None,
+        }
+    }
+
+    /// Enables explanations of security descriptors, resolving GUIDs using the given names.
+    pub fn set_guid_names(&mut self, guid_names: GuidNames) {
+        self.guid_names = Some(guid_names);
+    }
+
+    /// Enables the output of account names along with SIDs. The SIDs of each entry must be resolved
+    /// using [`Self::sid_cache_mut`] before it is written.
+    pub fn enable_sid_names(&mut self) {
+        self.sid_cache = Some(SidCache::default());
+    }
+
+    /// The cache of SID account names, if enabled.
+    pub fn sid_cache_mut(&mut self) -> Option<&mut SidCache> {
+        self.sid_cache.as_mut()
+    }
+
+    /// Outputs anything that must precede the first entry.
+    pub fn begin(&mut self) {
+        match self.format {
+            OutputFormat::Json => println!("["),
+            OutputFormat::Ldif => println!("version: 1"),
+            OutputFormat::Text|OutputFormat::JsonLines => {},
+        }
+    }
+
+    pub fn write_entry(&mut self, dn: &str, all_keys_values: &BTreeMap<String, Vec<LdapValue>>) -> Result<(), Error> {
+        self.write(dn, all_keys_values, None)
+    }
+
+    /// Outputs an entry along with the time at which it has been received.
+    pub fn write_received_entry(&mut self, dn: &str, all_keys_values: &BTreeMap<String, Vec<LdapValue>>, received: DateTime<Local>) -> Result<(), Error> {
+        let formatted = received.format("%Y-%m-%dT%H:%M:%S%.f%z").to_string();
+        self.write(dn, all_keys_values, Some(&formatted))
+    }
+
+    fn write(&mut self, dn: &str, all_keys_values: &BTreeMap<String, Vec<LdapValue>>, received: Option<&str>) -> Result<(), Error> {
+        let sid_names = self.sid_cache.as_ref().map(|sc| sc.names());
+        let explanations = match self.guid_names.as_ref() {
+            Some(gn) => explain_security_descriptors(all_keys_values, gn, sid_names.unwrap_or(&SidNames::new())),
+            None => BTreeMap::new(),
+        };
+        match self.format {
+            OutputFormat::Text => {
+                println!();
+                if let Some(received) = received {
+                    println!("# received: {}", received);
+                }
+                println!("dn: {}", dn);
+
+                let object_classes = all_keys_values
+                    .get("objectClass")
+                    .map(|oc| oc.as_slice())
+                    .unwrap_or(&[]);
+
+                for (key, values) in all_keys_values {
+                    output_values(key, values, object_classes, sid_names);
+                    for explanation in explanations.get(key.as_str()).into_iter().flatten() {
+                        println!("{};explained:::", key);
+                        for line in explanation {
+                            println!(" {}", line);
+                        }
+                    }
+                }
+            },
+            OutputFormat::Json => {
+                let json_entry = entry_to_json_extended(dn, all_keys_values, received, &explanations, sid_names)?;
+                let json_string = serde_json::to_string_pretty(&json_entry)
+                    .map_err(json_error)?;
+                if !self.is_first_entry {
+                    println!(",");
+                }
+                print!("{}", json_string);
+            },
+            OutputFormat::JsonLines => {
+                let json_entry = entry_to_json_extended(dn, all_keys_values, received, &explanations, sid_names)?;
+                let json_string = serde_json::to_string(&json_entry)
+                    .map_err(json_error)?;
+                println!("{}", json_string);
+            },
+            OutputFormat::Ldif => {
+                println!();
+                if let Some(received) = received {
+                    println!("# received: {}", received);
+                }
+                print!("{}", entry_to_ldif(dn, all_keys_values, self.ldif_comments, sid_names));
+                if self.ldif_comments {
+                    for (key, key_explanations) in &explanations {
+                        for explanation in key_explanations {
+                            println!("# {} explained:", key);
+                            for line in explanation {
+                                println!("#  {}", line);
+                            }
+                        }
+                    }
+                }
+            },
+        }
+        self.is_first_entry = false;
+        Ok(())
+    }
+
+    /// Outputs anything that must follow the last entry.
+    pub fn end(&mut self) {
+        if self.format == OutputFormat::Json {
+            if !self.is_first_entry {
+                println!();
+            }
+            println!("]");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use adldapsearch::values::{LdapValue, sids_in_value};
+use adldapsearch::values::structs::security::{Sid, SidNames};
+use ldap3::{Ldap, Scope, SearchEntry};
+use tokio::task::JoinSet;
+
+use crate::DEFAULT_FILTER;
+use crate::entry::entry_values;
+use crate::error::Error;
+
+
+/// The maximum number of SID lookups that are sent to the server at the same time.
+const MAX_CONCURRENT_LOOKUPS: usize = 8;
+
+
+/// Looks up the account name of a SID in the directory. Returns `None` if the SID does not belong
+/// to an object that the bound user may read.
+async fn look_up_sid(mut ldap: Ldap, sid: Sid) -> Result<(Sid, Option<String>), Error> {
+    // Active Directory accepts the SID of an object in place of its DN
+    let base_dn = format!("<SID={}>", sid);
+    let search_result = ldap.search(&base_dn, Scope::Base, DEFAULT_FILTER, &["msDS-PrincipalName"])
+        .await.map_err(Error::ldap("SID lookup"))?;
+    let results = match search_result.success() {
+        Ok((results, _response)) => results,
+        // e.g. no such object for foreign or deleted principals
+        Err(_) => return Ok((sid, None)),
+    };
+
+    let sid_string = sid.to_string();
+    for result_entry in results {
+        let values = entry_values(SearchEntry::construct(result_entry));
+        let principal_name = values.iter()
+            .find(|(k, _v)| k.eq_ignore_ascii_case("msDS-PrincipalName"))
+            .and_then(|(_k, v)| v.first());
+        if let Some(LdapValue::String(name)) = principal_name {
+            // objects that are not security principals have their SID string as principal name
+            if *name != sid_string {
+                let name = name.clone();
+                return Ok((sid, Some(name)));
+            }
+        }
+    }
+    Ok((sid, None))
+}
+
+
+/// The account names of the SIDs that have been resolved so far.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SidCache {
+    names: SidNames,
+
+    /// The SIDs that have been looked up without success, so that they are not looked up again.
+    unresolvable: BTreeSet<Sid>,
+}
+impl SidCache {
+    pub fn names(&self) -> &SidNames {
+        &self.names
+    }
+
+    /// Resolves the SIDs within the values of an entry to account names.
+    ///
+    /// Well-known SIDs are given their built-in names. SIDs that have been looked up before are
+    /// skipped; the others are looked up concurrently, at most [`MAX_CONCURRENT_LOOKUPS`] at a time.
+    pub async fn resolve(&mut self, ldap: &Ldap, all_keys_values: &BTreeMap<String, Vec<LdapValue>>) -> Result<(), Error> {
+        let mut unresolved = BTreeSet::new();
+        for (key, values) in all_keys_values {
+            for value in values {
+                for sid in sids_in_value(key, value) {
+                    if self.names.contains_key(&sid) || self.unresolvable.contains(&sid) {
+                        continue;
+                    }
+                    match sid.well_known_account_name() {
+                        Some(name) => {
+                            self.names.insert(sid, name.to_owned());
+                        },
+                        None => {
+                            unresolved.insert(sid);
+                        },
+                    }
+                }
+            }
+        }
+
+        let mut unresolved = unresolved.into_iter();
+        let mut lookups = JoinSet::new();
+        loop {
+            while lookups.len() < MAX_CONCURRENT_LOOKUPS {
+                let Some(sid) = unresolved.next() else { break };
+                lookups.spawn(look_up_sid(ldap.clone(), sid));
+            }
+            let Some(lookup_result) = lookups.join_next().await else { break };
+            let (sid, name) = lookup_result
+                .map_err(|e| Error::Io {
+                    context: "SID lookup failed".to_owned(),
+                    error: e.into(),
+                })??;
+            match name {
+                Some(n) => {
+                    self.names.insert(sid, n);
+                },
+                None => {
+                    self.unresolvable.insert(sid);
+                },
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::prelude::{BASE64_STANDARD, Engine};
+use ldap3::{Ldap, Scope, SearchEntry};
+use serde::{Deserialize, Serialize};
+
+use crate::{DEFAULT_FILTER, find_base_dn};
+use crate::connect::connect;
+use crate::controls::{
+    DIRSYNC_ANCESTORS_FIRST_ORDER, DIRSYNC_OBJECT_SECURITY, dirsync_control, DirSyncResponse,
+    show_deleted_control,
+};
+use crate::entry::{entry_values, retrieve_ranged_values};
+use crate::error::Error;
+use crate::opts::SyncOpts;
+use crate::output::EntryWriter;
+
+
+/// The state of a DirSync synchronization, stored between runs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct SyncState {
+    base_dn: String,
+    filter: String,
+
+    /// The attributes requested by the user; empty if all attributes are requested.
+    #[serde(default)]
+    attributes: Vec<String>,
+
+    #[serde(default)]
+    object_security: bool,
+
+    /// The cookie returned by the server, base64-encoded.
+    cookie: String,
+}
+impl SyncState {
+    /// Loads the state from the given file, returning `None` if it does not exist.
+    fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let state_string = match std::fs::read_to_string(path) {
+            Ok(ss) => ss,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Io {
+                context: format!("failed to read state file {}", path.display()),
+                error,
+            }),
+        };
+        toml::from_str(&state_string)
+            .map(Some)
+            .map_err(|e| Error::Parse {
+                context: format!("failed to parse state file {}", path.display()),
+                message: e.to_string(),
+            })
+    }
+
+    /// Stores the state in the given file.
+    ///
+    /// The state is written to a temporary file first, so that an interrupted write does not
+    /// destroy the previous state.
+    fn save(&self, path: &Path) -> Result<(), Error> {
+        let state_string = toml::to_string(self)
+            .map_err(|e| Error::Serialize {
+                context: format!("failed to serialize state for {}", path.display()),
+                message: e.to_string(),
+            })?;
+        let mut temp_path = PathBuf::from(path);
+        temp_path.as_mut_os_string().push(".tmp");
+        std::fs::write(&temp_path, state_string)
+            .and_then(|()| std::fs::rename(&temp_path, path))
+            .map_err(|error| Error::Io {
+                context: format!("failed to write state file {}", path.display()),
+                error,
+            })
+    }
+
+    fn cookie_bytes(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        BASE64_STANDARD.decode(&self.cookie)
+            .map_err(|e| Error::Parse {
+                context: format!("invalid cookie in state file {}", path.display()),
+                message: e.to_string(),
+            })
+    }
+}
+
+
+/// Outputs the changes since the previous run and updates the state file.
+pub(crate) async fn sync(o: SyncOpts) -> Result<(), Error> {
+    let previous_state = SyncState::load(&o.state_file)?;
+
+    let mut ldap = connect(&o.connection).await?;
+
+    let base_dn = match (o.base_dn.as_ref(), previous_state.as_ref()) {
+        (Some(bdn), _) => bdn.clone(),
+        (None, Some(ps)) => ps.base_dn.clone(),
+        (None, None) => find_base_dn(&mut ldap).await?,
+    };
+    let filter = match (o.filter.as_ref(), previous_state.as_ref()) {
+        (Some(f), _) => f.clone(),
+        (None, Some(ps)) => ps.filter.clone(),
+        (None, None) => DEFAULT_FILTER.to_owned(),
+    };
+    let requested_attributes = match previous_state.as_ref() {
+        Some(ps) if o.attributes.is_empty() => ps.attributes.clone(),
+        _ => o.attributes.clone(),
+    };
+    let mut cookie = match previous_state.as_ref() {
+        Some(ps) => {
+            if ps.base_dn != base_dn || ps.filter != filter {
+                return Err(Error::Usage(format!(
+                    "state file {} belongs to a synchronization of {:?} with filter {:?}; delete it to start over",
+                    o.state_file.display(), ps.base_dn, ps.filter,
+                )));
+            }
+            if ps.attributes != requested_attributes {
+                return Err(Error::Usage(format!(
+                    "state file {} belongs to a synchronization of the attributes {:?}; delete it to start over",
+                    o.state_file.display(), ps.attributes,
+                )));
+            }
+            if ps.object_security != o.object_security {
+                return Err(Error::Usage(format!(
+                    "state file {} belongs to a synchronization {} --object-security; delete it to start over",
+                    o.state_file.display(), if ps.object_security { "with" } else { "without" },
+                )));
+            }
+            ps.cookie_bytes(&o.state_file)?
+        },
+        None => Vec::new(),
+    };
+
+    let mut attributes = requested_attributes.clone();
+    let all_attributes = attributes.is_empty() || attributes.iter().any(|a| a == "*");
+    if !all_attributes && !attributes.iter().any(|a| a.eq_ignore_ascii_case("isDeleted")) {
+        attributes.push("isDeleted".to_owned());
+    }
+
+    let mut flags = DIRSYNC_ANCESTORS_FIRST_ORDER;
+    if o.object_security {
+        flags |= DIRSYNC_OBJECT_SECURITY;
+    }
+
+    let mut writer = EntryWriter::new(&o.output);
+    if o.resolve_sids {
+        writer.enable_sid_names();
+    }
+    writer.begin();
+    loop {
+        // the server returns the changes in batches; each batch comes with a new cookie
+        let more_results = match sync_batch(&mut ldap, &base_dn, &filter, &attributes, flags, &cookie, &mut writer).await {
+            Ok(response) => {
+                cookie = response.cookie;
+                response.more_results
+            },
+            Err(e) => {
+                writer.end();
+                return Err(e);
+            },
+        };
+
+        // store the state after each batch so that an interrupted synchronization can continue
+        let state = SyncState {
+            base_dn: base_dn.clone(),
+            filter: filter.clone(),
+            attributes: requested_attributes.clone(),
+            object_security: o.object_security,
+            cookie: BASE64_STANDARD.encode(&cookie),
+        };
+        if let Err(e) = state.save(&o.state_file) {
+            writer.end();
+            return Err(e);
+        }
+
+        if !more_results {
+            break;
+        }
+    }
+    writer.end();
+    Ok(())
+}
+
+
+async fn sync_batch(
+    ldap: &mut Ldap,
+    base_dn: &str,
+    filter: &str,
+    attributes: &[String],
+    flags: i64,
+    cookie: &[u8],
+    writer: &mut EntryWriter,
+) -> Result<DirSyncResponse, Error> {
+    ldap.with_controls(dirsync_control(flags, cookie));
+    let mut search_stream = ldap.streaming_search(base_dn, Scope::Subtree, filter, attributes)
+        .await.map_err(Error::ldap("DirSync search"))?;
+    while let Some(result_entry) = search_stream.next().await.map_err(Error::ldap("DirSync search"))? {
+        if result_entry.is_ref() {
+            continue;
+        }
+        let entry = SearchEntry::construct(result_entry);
+        let dn = entry.dn.clone();
+        let mut all_keys_values = entry_values(entry);
+        // DirSync also returns deleted objects
+        retrieve_ranged_values(ldap, &dn, &[show_deleted_control()], &mut all_keys_values).await?;
+        if let Some(sid_cache) = writer.sid_cache_mut() {
+            sid_cache.resolve(ldap, &all_keys_values).await?;
+        }
+        writer.write_entry(&dn, &all_keys_values)?;
+    }
+
+    let search_result = search_stream.finish().await;
+    let response = DirSyncResponse::find(&search_result.ctrls);
+    search_result
+        .success().map_err(Error::ldap("DirSync search"))?;
+    response
+        .ok_or_else(|| Error::Parse {
+            context: "failed to continue synchronization".to_owned(),
+            message: "the server did not return a valid DirSync response control".to_owned(),
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::SyncState;
+
+    #[test]
+    fn test_state_roundtrip() {
+        let path = std::env::temp_dir().join(format!("adldapsearch-sync-test-{}.toml", std::process::id()));
+        assert_eq!(SyncState::load(&path).unwrap(), None);
+
+        let state = SyncState {
+            base_dn: "DC=example,DC=com".to_owned(),
+            filter: "(objectClass=user)".to_owned(),
+            attributes: vec!["member".to_owned(), "memberOf".to_owned()],
+            object_security: true,
+            cookie: "TVNEUwM=".to_owned(),
+        };
+        state.save(&path).unwrap();
+        let loaded = SyncState::load(&path).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.cookie_bytes(&path).unwrap(), b"MSDS\x03");
+    }
+}
@@ -1,14 +1,19 @@
-pub(crate) mod bitmasks;
-pub(crate) mod enums;
-pub(crate) mod oids;
-pub(crate) mod structs;
+pub mod bitmasks;
+pub mod enums;
+pub mod json;
+pub mod ldif;
+pub mod oids;
+pub mod structs;
 
 
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
 use std::sync::LazyLock;
 
 use base64::prelude::{BASE64_STANDARD, Engine};
 use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
 use regex::Regex;
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::values::bitmasks::{
@@ -44,7 +49,7 @@ use crate::values::structs::replication::{
 };
 use crate::values::structs::schema::{PrefixMap, SchemaInfo};
 use crate::values::structs::security::{
-    CachedMembership, logon_hours_to_string, RidPool, SecurityDescriptor,
+    CachedMembership, logon_hours_to_string, RidPool, SecurityDescriptor, Sid, SidNames,
 };
 use crate::values::structs::security::key_credential_link::KeyCredentialLinkBlob;
 use crate::values::structs::terminal_services::UserParameters;
@@ -70,6 +75,20 @@ static AD_TIMESTAMP_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(concat!(
 )).expect("failed to parse AD timestamp regex"));
 
 
+/// The attributes whose values are SIDs.
+pub const SID_ATTRIBUTES: [&str; 11] = [
+    "mS-DS-CreatorSID", "msDS-LdapQosPolicyTarget", "msDS-ServiceAccountSID",
+    "msDS-ShadowPrincipalSid", "msExchMasterAccountSid", "objectSid", "securityIdentifier",
+    "sidHistory", "tokenGroups", "tokenGroupsGlobalAndUniversal", "tokenGroupsNoGCAcceptable",
+];
+
+/// The attributes whose values are security descriptors.
+pub const SECURITY_DESCRIPTOR_ATTRIBUTES: [&str; 7] = [
+    "fRSRootSecurity", "msExchLogonACL", "msExchMailboxSecurityDescriptor", "msExchPFDefaultAdminACL",
+    "msExchSubmitRelaySD", "nTSecurityDescriptor", "pKIEnrollmentAccess",
+];
+
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum LdapValue {
     String(String),
@@ -104,7 +123,7 @@ fn is_safe_ldap_string(string: &str) -> bool {
 }
 
 
-pub(crate) fn output_string_value_as_string(key: &str, str_value: &str) {
+pub fn output_string_value_as_string(key: &str, str_value: &str) {
     if is_safe_ldap_string(&str_value) {
         println!("{}: {}", key, str_value);
     } else {
@@ -113,7 +132,7 @@ pub(crate) fn output_string_value_as_string(key: &str, str_value: &str) {
 }
 
 
-pub(crate) fn output_binary_value_as_hexdump(key: &str, bin_value: &[u8]) {
+pub fn output_binary_value_as_hexdump(key: &str, bin_value: &[u8]) {
     println!("{}:::", key);
     let mut offset = 0;
     for chunk in bin_value.chunks(16) {
@@ -130,11 +149,72 @@ pub(crate) fn output_binary_value_as_hexdump(key: &str, bin_value: &[u8]) {
 }
 
 
-fn output_timestamp_value(key: &str, value: &str) {
+/// A destination for attribute values, whether decoded or not.
+///
+/// The decoding functions in this module pass each value to one of these methods depending on how
+/// it is best displayed as text; implementations that do not output text can ignore the textual
+/// representation and use the decoded value instead.
+pub trait ValueOutput {
+    /// Outputs a string value that could not be decoded any further.
+    fn string(&mut self, key: &str, value: &str);
+
+    /// Outputs a binary value that could not be decoded any further.
+    fn binary(&mut self, key: &str, value: &[u8]);
+
+    /// Outputs a string value followed by a short annotation describing its meaning.
+    fn annotated<T: Serialize + ?Sized>(&mut self, key: &str, value: &str, annotation: &str, decoded: &T);
+
+    /// Outputs a single-line textual representation in place of the original value.
+    fn replaced<T: Serialize + ?Sized>(&mut self, key: &str, text: &str, decoded: &T);
+
+    /// Outputs a multi-line textual representation in place of the original value.
+    fn block<T: Serialize + ?Sized>(&mut self, key: &str, text: &str, decoded: &T);
+
+    /// The account names of SIDs to include in the textual representations, if any.
+    fn sid_names(&self) -> Option<&SidNames>;
+}
+
+
+/// Outputs values as LDIF-like text to standard output.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TextOutput<'a> {
+    pub sid_names: Option<&'a SidNames>,
+}
+impl<'a> ValueOutput for TextOutput<'a> {
+    fn string(&mut self, key: &str, value: &str) {
+        output_string_value_as_string(key, value);
+    }
+
+    fn binary(&mut self, key: &str, value: &[u8]) {
+        output_binary_value_as_hexdump(key, value);
+    }
+
+    fn annotated<T: Serialize + ?Sized>(&mut self, key: &str, value: &str, annotation: &str, _decoded: &T) {
+        println!("{}: {} ({})", key, value, annotation);
+    }
+
+    fn replaced<T: Serialize + ?Sized>(&mut self, key: &str, text: &str, _decoded: &T) {
+        println!("{}: {}", key, text);
+    }
+
+    fn block<T: Serialize + ?Sized>(&mut self, key: &str, text: &str, _decoded: &T) {
+        println!("{}:::", key);
+        for line in text.split("\n") {
+            println!(" {}", line);
+        }
+    }
+
+    fn sid_names(&self) -> Option<&SidNames> {
+        self.sid_names
+    }
+}
+
+
+fn output_timestamp_value<O: ValueOutput>(out: &mut O, key: &str, value: &str) {
     let parsed = match i64::from_str_radix(value, 10) {
         Ok(p) => p,
         Err(_) => {
-            output_string_value_as_string(key, value);
+            out.string(key, value);
             return;
         },
     };
@@ -142,20 +222,21 @@ fn output_timestamp_value(key: &str, value: &str) {
     let remaining_nanoseconds = TimeDelta::nanoseconds((parsed % 10) * 100);
     let timestamp_utc = WINDOWS_EPOCH + microseconds + remaining_nanoseconds;
     let timestamp_local = timestamp_utc.with_timezone(&Local);
-    println!("{}: {} ({})", key, value, timestamp_local.format("%Y-%m-%dT%H:%M:%S%.f%z"));
+    let formatted = timestamp_local.format("%Y-%m-%dT%H:%M:%S%.f%z").to_string();
+    out.annotated(key, value, &formatted, &timestamp_utc);
 }
 
 
-fn output_negative_interval_value(key: &str, value: &str) {
+fn output_negative_interval_value<O: ValueOutput>(out: &mut O, key: &str, value: &str) {
     let parsed = match i64::from_str_radix(value, 10) {
         Ok(p) => p,
         Err(_) => {
-            output_string_value_as_string(key, value);
+            out.string(key, value);
             return;
         },
     };
     if parsed == i64::MIN {
-        println!("{}: {} (never)", key, value);
+        out.annotated(key, value, "never", "never");
         return;
     }
 
@@ -170,39 +251,40 @@ fn output_negative_interval_value(key: &str, value: &str) {
     let (hours, rest) = (rest % 24, rest / 24);
     let days = rest;
 
-    println!("{}: {} ({}d {}h {}min {}s)", key, value, days, hours, minutes, seconds);
+    let formatted = format!("{}d {}h {}min {}s", days, hours, minutes, seconds);
+    out.annotated(key, value, &formatted, &formatted);
 }
 
 
-fn output_guid_value(key: &str, value: &[u8]) {
+fn output_guid_value<O: ValueOutput>(out: &mut O, key: &str, value: &[u8]) {
     let bytes: [u8; 16] = match value.try_into() {
         Ok(bs) => bs,
         Err(_) => {
             // wrong length for a GUID
-            output_binary_value_as_hexdump(key, value);
+            out.binary(key, value);
             return;
         },
     };
     let uuid = Uuid::from_bytes_le(bytes);
-    println!("{}: {}", key, uuid);
+    out.replaced(key, &uuid.to_string(), &uuid);
 }
 
 
-fn output_sid_value(key: &str, value: &[u8]) {
+fn output_sid_value<O: ValueOutput>(out: &mut O, key: &str, value: &[u8]) {
     if value.len() < 8 {
-        output_binary_value_as_hexdump(key, value);
+        out.binary(key, value);
         return;
     }
     let version = value[0];
     if version != 0x01 {
         // we only know how to output version 1 SIDs
-        output_binary_value_as_hexdump(key, value);
+        out.binary(key, value);
         return;
     }
     let num_subs: usize = value[1].into();
     if value.len() != 8 + 4*num_subs {
         // incorrect number of subauthority elements for this subauthority count
-        output_binary_value_as_hexdump(key, value);
+        out.binary(key, value);
         return;
     }
 
@@ -216,33 +298,38 @@ fn output_sid_value(key: &str, value: &[u8]) {
         | (u64::from(value[7]) <<  0)
     ;
 
-    print!("{}: S-{}-{}", key, version, authority);
+    let mut sid_string = format!("S-{}-{}", version, authority);
     for i in 0..num_subs {
         let sub_auth = u32::from_le_bytes(value[(8 + 4*i)..(8 + 4*i + 4)].try_into().unwrap());
-        print!("-{}", sub_auth);
+        write!(sid_string, "-{}", sub_auth).unwrap();
+    }
+    let name = Sid::try_from_bytes(value)
+        .and_then(|sid| out.sid_names()?.get(&sid).cloned());
+    match name {
+        Some(name) => out.replaced(key, &format!("{} ({})", sid_string, name), &sid_string),
+        None => out.replaced(key, &sid_string, &sid_string),
     }
-    println!();
 }
 
 
-fn output_oid(key: &str, str_value: &str) {
+fn output_oid<O: ValueOutput>(out: &mut O, key: &str, str_value: &str) {
     if let Some(known_oid) = KNOWN_OIDS.get(str_value) {
-        println!("{}: {} ({})", key, str_value, known_oid);
+        out.annotated(key, str_value, known_oid, known_oid);
     } else {
-        output_string_value_as_string(key, str_value);
+        out.string(key, str_value);
     }
 }
 
 
-fn output_utf16_string_with_bom(key: &str, bin_value: &[u8]) {
+fn output_utf16_string_with_bom<O: ValueOutput>(out: &mut O, key: &str, bin_value: &[u8]) {
     if bin_value.len() % 2 != 0 {
         // invalid UTF-16
-        output_binary_value_as_hexdump(key, bin_value);
+        out.binary(key, bin_value);
         return;
     }
     if bin_value.len() < 2 {
         // missing BOM
-        output_binary_value_as_hexdump(key, bin_value);
+        out.binary(key, bin_value);
         return;
     }
 
@@ -252,7 +339,7 @@ fn output_utf16_string_with_bom(key: &str, bin_value: &[u8]) {
         0xFFFE => false,
         _ => {
             // invalid BOM
-            output_binary_value_as_hexdump(key, bin_value);
+            out.binary(key, bin_value);
             return;
         },
     };
@@ -269,264 +356,267 @@ fn output_utf16_string_with_bom(key: &str, bin_value: &[u8]) {
     }
     let Ok(mut string) = String::from_utf16(&words) else {
         // invalid UTF-16
-        output_binary_value_as_hexdump(key, bin_value);
+        out.binary(key, bin_value);
         return;
     };
     string = string.replace("\r\n", "\n").replace("\r", "\n");
-    println!("{}:::", key);
-    for line in string.split("\n") {
-        println!(" {}", line);
-    }
+    out.block(key, &string, &string);
 }
 
 
 macro_rules! output_as_enum {
-    (@bytes, $key:expr, $value:expr, $enum:ty) => {
+    (@bytes, $out:expr, $key:expr, $value:expr, $enum:ty) => {
         if let Some(enum_val) = <$enum>::try_from_bytes($value) {
-            println!("{}: {:?}", $key, enum_val);
+            $out.replaced($key, &format!("{:?}", enum_val), &enum_val);
         } else {
-            output_binary_value_as_hexdump($key, $value);
+            $out.binary($key, $value);
         }
     };
-    (@string, $key:expr, $value:expr, $enum:ty) => {
+    (@string, $out:expr, $key:expr, $value:expr, $enum:ty) => {
         if let Some(enum_val) = <$enum>::try_from_str($value) {
-            println!("{}: {} ({:?})", $key, $value, enum_val);
+            $out.annotated($key, $value, &format!("{:?}", enum_val), &enum_val);
         } else {
-            output_string_value_as_string($key, $value);
+            $out.string($key, $value);
         }
     };
-    ($key:expr, $value:expr, $int_type:ty, $enum:ty) => {
+    ($out:expr, $key:expr, $value:expr, $int_type:ty, $enum:ty) => {
         if let Ok(int_val) = <$int_type>::from_str_radix($value, 10) {
             #[allow(irrefutable_let_patterns)]
             if int_val == 0 {
-                println!("{}: {}", $key, int_val);
+                $out.string($key, &int_val.to_string());
             } else if let Ok(enum_val) = <$enum>::try_from(int_val) {
-                println!("{}: {} ({:?})", $key, int_val, enum_val);
+                $out.annotated($key, &int_val.to_string(), &format!("{:?}", enum_val), &enum_val);
             } else {
-                output_string_value_as_string($key, $value);
+                $out.string($key, $value);
             }
         } else {
-            output_string_value_as_string($key, $value);
+            $out.string($key, $value);
         }
     };
 }
 
 macro_rules! output_as_bitflags {
-    ($key:expr, $value:expr, $int_type:ty, $enum:ty) => {
+    ($out:expr, $key:expr, $value:expr, $int_type:ty, $enum:ty) => {
         if let Ok(int_val) = <$int_type>::from_str_radix($value, 10) {
             #[allow(irrefutable_let_patterns)]
             if int_val == 0 {
-                println!("{}: {}", $key, int_val);
+                $out.string($key, &int_val.to_string());
             } else {
                 let enum_val = <$enum>::from_bits_retain(int_val);
-                println!("{}: {} ({:?})", $key, int_val, enum_val);
+                $out.annotated($key, &int_val.to_string(), &format!("{:?}", enum_val), &enum_val);
             }
         } else {
-            output_string_value_as_string($key, $value);
+            $out.string($key, $value);
         }
     };
 }
 
 macro_rules! output_as_struct {
-    ($key:expr, $value:expr, $struct:ty) => {
-        output_as_struct!(@perform, try_from_bytes, output_binary_value_as_hexdump, $key, $value, $struct)
+    ($out:expr, $key:expr, $value:expr, $struct:ty) => {
+        output_as_struct!(@perform, try_from_bytes, binary, $out, $key, $value, $struct)
     };
-    (@string, $key:expr, $value:expr, $struct:ty) => {
-        output_as_struct!(@perform, try_from_str, output_string_value_as_string, $key, $value, $struct)
+    (@string, $out:expr, $key:expr, $value:expr, $struct:ty) => {
+        output_as_struct!(@perform, try_from_str, string, $out, $key, $value, $struct)
     };
-    (@perform, $try_from_func:ident, $otherwise_output_func:ident, $key:expr, $value:expr, $struct:ty) => {
+    (@perform, $try_from_func:ident, $otherwise_output_func:ident, $out:expr, $key:expr, $value:expr, $struct:ty) => {
         if let Some(struct_val) = <$struct>::$try_from_func($value) {
             let formatted = format!("{:#?}", struct_val);
-            println!("{}:::", $key);
-            for line in formatted.split("\n") {
-                println!(" {}", line);
+            $out.block($key, &formatted, &struct_val);
+        } else {
+            $out.$otherwise_output_func($key, $value);
+        }
+    };
+    (@sids, $out:expr, $key:expr, $value:expr, $struct:ty) => {
+        if let Some(struct_val) = <$struct>::try_from_bytes($value) {
+            let mut formatted = format!("{:#?}", struct_val);
+            for named_sid in named_sids(struct_val.sids(), $out.sid_names()) {
+                formatted.push('\n');
+                formatted.push_str(&named_sid);
             }
+            $out.block($key, &formatted, &struct_val);
         } else {
-            $otherwise_output_func($key, $value);
+            $out.binary($key, $value);
         }
     };
 }
 
 macro_rules! output_stringification_result {
-    ($key:expr, $value:expr, $string_func:ident) => {
+    ($out:expr, $key:expr, $value:expr, $string_func:ident) => {
         if let Some(mut string_val) = $string_func($value) {
             string_val = string_val.replace("\r\n", "\n").replace("\r", "\n");
-            println!("{}:::", $key);
-            for line in string_val.split("\n") {
-                println!(" {}", line);
-            }
+            $out.block($key, &string_val, &string_val);
         } else {
-            output_binary_value_as_hexdump($key, $value);
+            $out.binary($key, $value);
         }
     };
 }
 
-pub(crate) fn output_special_string_value(key: &str, value: &str, object_classes: &[LdapValue]) -> bool {
+pub fn output_special_string_value<O: ValueOutput>(out: &mut O, key: &str, value: &str, object_classes: &[LdapValue]) -> bool {
     if key == "userAccountControl" || key == "msDs-User-Account-Control-Computed"
             || key == "msExchUserAccountControl" {
-        output_as_bitflags!(key, value, u32, UserAccountControl);
+        output_as_bitflags!(out, key, value, u32, UserAccountControl);
         true
     } else if key == "groupType" {
-        output_as_bitflags!(key, value, i32, GroupType);
+        output_as_bitflags!(out, key, value, i32, GroupType);
         true
     } else if key == "sAMAccountType" {
-        output_as_enum!(key, value, u32, SamAccountType);
+        output_as_enum!(out, key, value, u32, SamAccountType);
         true
     } else if key == "domainControllerFunctionality" || key == "domainFunctionality"
             || key == "forestFunctionality" || key == "msDS-Behavior-Version"
             || key == "msDS-RequiredDomainBehaviorVersion"
             || key == "msDS-RequiredForestBehaviorVersion" {
-        output_as_enum!(key, value, u32, FunctionalityLevel);
+        output_as_enum!(out, key, value, u32, FunctionalityLevel);
         true
     } else if key == "systemFlags" {
         if object_classes.iter().any(|oc| oc.is_string("crossRef")) {
-            output_as_bitflags!(key, value, i32, CrossRefSystemFlags);
+            output_as_bitflags!(out, key, value, i32, CrossRefSystemFlags);
         } else if object_classes.iter().any(|oc| oc.is_string("classSchema")) {
-            output_as_bitflags!(key, value, i32, ClassSchemaSystemFlags);
+            output_as_bitflags!(out, key, value, i32, ClassSchemaSystemFlags);
         } else if object_classes.iter().any(|oc| oc.is_string("attributeSchema")) {
-            output_as_bitflags!(key, value, i32, AttributeSchemaSystemFlags);
+            output_as_bitflags!(out, key, value, i32, AttributeSchemaSystemFlags);
         } else {
-            output_as_bitflags!(key, value, i32, GenericSystemFlags);
+            output_as_bitflags!(out, key, value, i32, GenericSystemFlags);
         }
         true
     } else if key == "instanceType" {
-        output_as_bitflags!(key, value, u32, InstanceType);
+        output_as_bitflags!(out, key, value, u32, InstanceType);
         true
     } else if key == "msDS-ReplAuthenticationMode" {
-        output_as_enum!(key, value, u32, ReplAuthenticationMode);
+        output_as_enum!(out, key, value, u32, ReplAuthenticationMode);
         true
     } else if key == "msDS-OptionalFeatureFlags" {
-        output_as_bitflags!(key, value, u32, OptionalFeatureFlags);
+        output_as_bitflags!(out, key, value, u32, OptionalFeatureFlags);
         true
     } else if key == "objectClassCategory" {
-        output_as_enum!(key, value, u32, ObjectClassCategory);
+        output_as_enum!(out, key, value, u32, ObjectClassCategory);
         true
     } else if key == "oMSyntax" {
-        output_as_enum!(key, value, u32, OmSyntax);
+        output_as_enum!(out, key, value, u32, OmSyntax);
         true
     } else if key == "attributeSyntax" {
-        output_as_enum!(@string, key, value, AttributeSyntax);
+        output_as_enum!(@string, out, key, value, AttributeSyntax);
         true
     } else if key == "options" {
         if object_classes.iter().any(|oc| oc.is_string("interSiteTransport")) {
-            output_as_bitflags!(key, value, u32, InterSiteTransportOptions);
+            output_as_bitflags!(out, key, value, u32, InterSiteTransportOptions);
             true
         } else if object_classes.iter().any(|oc| oc.is_string("nTDSConnection")) {
-            output_as_bitflags!(key, value, u32, DsConnectionOptions);
+            output_as_bitflags!(out, key, value, u32, DsConnectionOptions);
             true
         } else if object_classes.iter().any(|oc| oc.is_string("nTDSDSA")) {
-            output_as_bitflags!(key, value, u32, DsaSettingsOptions);
+            output_as_bitflags!(out, key, value, u32, DsaSettingsOptions);
             true
         } else if object_classes.iter().any(|oc| oc.is_string("ntDSSiteSettings")) {
-            output_as_bitflags!(key, value, u32, SiteSettingsOptions);
+            output_as_bitflags!(out, key, value, u32, SiteSettingsOptions);
             true
         } else if object_classes.iter().any(|oc| oc.is_string("siteConnection") || oc.is_string("siteLink")) {
-            output_as_bitflags!(key, value, u32, SiteConnectionOptions);
+            output_as_bitflags!(out, key, value, u32, SiteConnectionOptions);
             true
         } else {
             false
         }
     } else if key == "primaryGroupID" {
-        output_as_enum!(key, value, u32, Rid);
+        output_as_enum!(out, key, value, u32, Rid);
         true
     } else if key == "pwdProperties" {
-        output_as_bitflags!(key, value, u32, PasswordProperties);
+        output_as_bitflags!(out, key, value, u32, PasswordProperties);
         true
     } else if key == "searchFlags" {
-        output_as_bitflags!(key, value, u32, SearchFlags);
+        output_as_bitflags!(out, key, value, u32, SearchFlags);
         true
     } else if key == "serverState" {
-        output_as_enum!(key, value, u32, ServerState);
+        output_as_enum!(out, key, value, u32, ServerState);
         true
     } else if key == "msDS-SupportedEncryptionTypes" {
-        output_as_bitflags!(key, value, u32, SupportedEncryptionTypes);
+        output_as_bitflags!(out, key, value, u32, SupportedEncryptionTypes);
         true
     } else if key == "trustAttributes" {
-        output_as_bitflags!(key, value, u32, TrustAttributes);
+        output_as_bitflags!(out, key, value, u32, TrustAttributes);
         true
     } else if key == "trustDirection" {
-        output_as_bitflags!(key, value, u32, TrustDirection);
+        output_as_bitflags!(out, key, value, u32, TrustDirection);
         true
     } else if key == "trustType" {
-        output_as_enum!(key, value, u32, TrustType);
+        output_as_enum!(out, key, value, u32, TrustType);
         true
     } else if key == "dSCorePropagationData" {
-        output_as_struct!(@string, key, value, DsCorePropagationData);
+        output_as_struct!(@string, out, key, value, DsCorePropagationData);
         true
     } else if key == "msDS-KeyCredentialLink" {
-        output_as_struct!(@string, key, value, KeyCredentialLinkBlob);
+        output_as_struct!(@string, out, key, value, KeyCredentialLinkBlob);
         true
     } else if key == "rIDAllocationPool" || key == "rIDAvailablePool"
             || key == "rIDPreviousAllocationPool" || key == "rIDUsedPool" {
-        output_as_struct!(@string, key, value, RidPool);
+        output_as_struct!(@string, out, key, value, RidPool);
         true
     } else if key == "deletedItemFlags" {
-        output_as_enum!(key, value, u32, DeletedItemFlags);
+        output_as_enum!(out, key, value, u32, DeletedItemFlags);
         true
     } else if key == "msExchAddressBookFlags" {
-        output_as_bitflags!(key, value, i32, AddressBookFlags);
+        output_as_bitflags!(out, key, value, i32, AddressBookFlags);
         true
     } else if key == "msExchAuditAdmin" || key == "msExchAuditDelegate"
             || key == "msExchAuditDelegateAdmin" || key == "msExchAuditOwner" {
-        output_as_bitflags!(key, value, i32, MailboxAuditOperations);
+        output_as_bitflags!(out, key, value, i32, MailboxAuditOperations);
         true
     } else if key == "msExchCapabilityIdentifiers" {
-        output_as_enum!(key, value, u32, CapabilityIdentifier);
+        output_as_enum!(out, key, value, u32, CapabilityIdentifier);
         true
     } else if key == "msExchDeviceClientType" {
-        output_as_bitflags!(key, value, i32, DeviceClientType);
+        output_as_bitflags!(out, key, value, i32, DeviceClientType);
         true
     } else if key == "msExchGroupDepartRestriction" || key == "msExchGroupJoinRestriction" {
-        output_as_enum!(key, value, u32, GroupJoinLeaveRestriction);
+        output_as_enum!(out, key, value, u32, GroupJoinLeaveRestriction);
         true
     } else if key == "msExchELCMailboxFlags" {
-        output_as_bitflags!(key, value, u32, ElcMailboxFlags);
+        output_as_bitflags!(out, key, value, u32, ElcMailboxFlags);
         true
     } else if key == "msExchGroupSecurityFlags" {
-        output_as_struct!(@string, key, value, GroupSecurityFlags);
+        output_as_struct!(@string, out, key, value, GroupSecurityFlags);
         true
     } else if key == "msExchLocalizationFlags" {
-        output_as_bitflags!(key, value, i32, LocalizationFlags);
+        output_as_bitflags!(out, key, value, i32, LocalizationFlags);
         true
     } else if key == "msExchMailboxFolderSet" {
-        output_as_bitflags!(key, value, i32, MailboxFolderSet);
+        output_as_bitflags!(out, key, value, i32, MailboxFolderSet);
         true
     } else if key == "msExchMailboxFolderSet2" {
-        output_as_struct!(@string, key, value, MailboxFolderSet2);
+        output_as_struct!(@string, out, key, value, MailboxFolderSet2);
         true
     } else if key == "msExchMobileMailboxFlags" {
-        output_as_bitflags!(key, value, i32, MobileMailboxFlags);
+        output_as_bitflags!(out, key, value, i32, MobileMailboxFlags);
         true
     } else if key == "msExchModerationFlags" {
-        output_as_bitflags!(key, value, i32, ModerationFlags);
+        output_as_bitflags!(out, key, value, i32, ModerationFlags);
         true
     } else if key == "msExchProvisioningFlags" {
-        output_as_bitflags!(key, value, u32, ProvisioningFlags);
+        output_as_bitflags!(out, key, value, u32, ProvisioningFlags);
         true
     } else if key == "msExchRecipientDisplayType" {
-        output_as_enum!(key, value, i32, RecipientDisplayType);
+        output_as_enum!(out, key, value, i32, RecipientDisplayType);
         true
     } else if key == "msExchRecipientSoftDeletedStatus" {
-        output_as_bitflags!(key, value, u32, SoftDeletedStatus);
+        output_as_bitflags!(out, key, value, u32, SoftDeletedStatus);
         true
     } else if key == "msExchRecipientTypeDetails" || key == "msExchPreviousRecipientTypeDetails" {
-        output_as_bitflags!(key, value, i64, RecipientTypeDetails);
+        output_as_bitflags!(out, key, value, i64, RecipientTypeDetails);
         true
     } else if key == "msExchRoleGroupType" {
-        output_as_enum!(key, value, i32, RoleGroupType);
+        output_as_enum!(out, key, value, i32, RoleGroupType);
         true
     } else if key == "msExchTextMessagingState" {
-        output_as_struct!(@string, key, value, TextMessagingState);
+        output_as_struct!(@string, out, key, value, TextMessagingState);
         true
     } else if key == "msExchTransportRecipientSettingsFlags" {
-        output_as_bitflags!(key, value, i32, TransportSettingFlags);
+        output_as_bitflags!(out, key, value, i32, TransportSettingFlags);
         true
     } else if key == "msExchVersion" {
-        output_as_struct!(@string, key, value, ExchangeVersion);
+        output_as_struct!(@string, out, key, value, ExchangeVersion);
         true
     } else if key == "internetEncoding" {
         // technically Exchange as well
-        output_as_struct!(@string, key, value, InternetEncoding);
+        output_as_struct!(@string, out, key, value, InternetEncoding);
         true
     } else if key == "accountExpires" || key == "badPasswordTime" || key == "creationTime"
             || key == "lastLogoff" || key == "lastLogon" || key == "lastLogonTimestamp"
@@ -537,17 +627,17 @@ pub(crate) fn output_special_string_value(key: &str, value: &str, object_classes
             || key == "msDS-LastFailedInteractiveLogonTime"
             || key == "msDS-UserPasswordExpiryTimeComputed"
             || key == "pwdLastSet" {
-        output_timestamp_value(key, value);
+        output_timestamp_value(out, key, value);
         true
     } else if key == "supportedCapabilities" || key == "supportedControl"
             || key == "supportedExtension" {
-        output_oid(key, value);
+        output_oid(out, key, value);
         true
     } else if key == "lockoutDuration" || key == "lockOutObservationWindow" || key == "maxPwdAge"
             || key == "minPwdAge" || key == "msDS-LockoutDuration"
             || key == "msDS-LockoutObservationWindow" || key == "msDS-MaximumPasswordAge"
             || key == "msDS-MinimumPasswordAge" || key == "forceLogoff" {
-        output_negative_interval_value(key, value);
+        output_negative_interval_value(out, key, value);
         true
     } else {
         false
@@ -564,7 +654,21 @@ pub(crate) fn output_special_string_value(key: &str, value: &str, object_classes
 }
 
 
-pub(crate) fn output_special_binary_value(key: &str, value: &[u8]) -> bool {
+/// Lists the SIDs whose account names are known as `SID (name)`, skipping duplicates.
+fn named_sids<'a, I: IntoIterator<Item = &'a Sid>>(sids: I, sid_names: Option<&SidNames>) -> Vec<String> {
+    let Some(sid_names) = sid_names else {
+        return Vec::new();
+    };
+    let mut seen = BTreeSet::new();
+    sids.into_iter()
+        .filter(|sid| seen.insert(*sid))
+        .filter(|sid| sid_names.contains_key(sid))
+        .map(|sid| sid.to_string_with_name(sid_names))
+        .collect()
+}
+
+
+pub fn output_special_binary_value<O: ValueOutput>(out: &mut O, key: &str, value: &[u8]) -> bool {
     if key == "attributeSecurityGUID" || key == "invocationId" || key == "mS-DS-ConsistencyGuid"
             || key == "msDFS-GenerationGUIDv2" || key == "msDFS-LinkIdentityGUIDv2"
             || key == "msDFS-NamespaceIdentityGUIDv2" || key == "msDFSR-ContentSetGuid"
@@ -572,79 +676,79 @@ pub(crate) fn output_special_binary_value(key: &str, value: &[u8]) -> bool {
             || key == "msDS-OptionalFeatureGuid" || key == "msExchMailboxGuid"
             || key == "netbootGuid" || key == "objectGUID" || key == "parentGUID"
             || key == "schemaIDGUID" || key == "serverClassID" {
-        output_guid_value(key, value);
+        output_guid_value(out, key, value);
         true
-    } else if key == "mS-DS-CreatorSID" || key == "msDS-LdapQosPolicyTarget"
-            || key == "msDS-ServiceAccountSID" || key == "msDS-ShadowPrincipalSid"
-            || key == "msExchMasterAccountSid" || key == "objectSid" || key == "securityIdentifier"
-            || key == "sidHistory" || key == "tokenGroups" || key == "tokenGroupsGlobalAndUniversal"
-            || key == "tokenGroupsNoGCAcceptable" {
-        output_sid_value(key, value);
+    } else if SID_ATTRIBUTES.contains(&key) {
+        output_sid_value(out, key, value);
         true
     } else if key == "replUpToDateVector" {
-        output_as_struct!(key, value, ReplUpToDateVector2);
+        output_as_struct!(out, key, value, ReplUpToDateVector2);
         true
     } else if key == "repsFrom" || key == "repsTo" {
-        output_as_struct!(key, value, RepsFromTo);
+        output_as_struct!(out, key, value, RepsFromTo);
         true
     } else if key == "dNSProperty" {
-        output_as_struct!(key, value, DnsProperty);
+        output_as_struct!(out, key, value, DnsProperty);
         true
     } else if key == "dnsRecord" {
-        output_as_struct!(key, value, DnsRecord);
+        output_as_struct!(out, key, value, DnsRecord);
         true
     } else if key == "dSASignature" {
-        output_as_struct!(key, value, DsaSignatureState1);
+        output_as_struct!(out, key, value, DsaSignatureState1);
         true
     } else if key == "msDS-TrustForestTrustInfo" {
-        output_as_struct!(key, value, TrustForestTrustInfo);
+        output_as_struct!(@sids, out, key, value, TrustForestTrustInfo);
         true
     } else if key == "partialAttributeSet" {
-        output_as_struct!(key, value, PartialAttributeSet);
+        output_as_struct!(out, key, value, PartialAttributeSet);
         true
     } else if key == "msDS-Site-Affinity" {
-        output_as_struct!(key, value, SiteAffinity);
+        output_as_struct!(out, key, value, SiteAffinity);
         true
     } else if key == "msDS-Cached-Membership" {
-        output_as_struct!(key, value, CachedMembership);
+        output_as_struct!(@sids, out, key, value, CachedMembership);
         true
     } else if key == "prefixMap" {
-        output_as_struct!(key, value, PrefixMap);
+        output_as_struct!(out, key, value, PrefixMap);
         true
     } else if key == "replPropertyMetaData" {
-        output_as_struct!(key, value, ReplPropertyMetaData);
+        output_as_struct!(out, key, value, ReplPropertyMetaData);
         true
     } else if key == "schemaInfo" {
-        output_as_struct!(key, value, SchemaInfo);
+        output_as_struct!(out, key, value, SchemaInfo);
         true
-    } else if key == "fRSRootSecurity" || key == "msExchLogonACL"
-            || key == "msExchMailboxSecurityDescriptor" || key == "msExchPFDefaultAdminACL"
-            || key == "msExchSubmitRelaySD" || key == "nTSecurityDescriptor"
-            || key == "pKIEnrollmentAccess" {
+    } else if SECURITY_DESCRIPTOR_ATTRIBUTES.contains(&key) {
         if let Some(sd) = SecurityDescriptor::try_from_bytes(value) {
-            if let Some(sd_string) = sd.try_to_string() {
-                println!("{}: {}", key, sd_string);
+            if let Some(mut sd_string) = sd.try_to_string() {
+                // SIDs with an SDDL alias are already legible
+                let sids = sd.sids().into_iter()
+                    .filter(|sid| sid.as_well_known_sddl_sid_string().is_none());
+                let named = named_sids(sids, out.sid_names());
+                if !named.is_empty() {
+                    write!(sd_string, " # {}", named.join(", ")).unwrap();
+                }
+                out.replaced(key, &sd_string, &sd);
             } else {
-                output_binary_value_as_hexdump(key, value);
+                out.binary(key, value);
             }
         } else {
-            output_binary_value_as_hexdump(key, value);
+            out.binary(key, value);
         }
         true
     } else if key == "msDFS-TargetListv2" {
-        output_utf16_string_with_bom(key, value);
+        output_utf16_string_with_bom(out, key, value);
         true
     } else if key == "logonHours" {
-        output_stringification_result!(key, value, logon_hours_to_string);
+        output_stringification_result!(out, key, value, logon_hours_to_string);
         true
     } else if key == "msDFSR-Schedule" {
-        output_stringification_result!(key, value, dfsr_schedule_to_string);
+        output_stringification_result!(out, key, value, dfsr_schedule_to_string);
         true
     } else if key == "oMObjectClass" {
-        output_as_enum!(@bytes, key, value, OmObjectClass);
+        output_as_enum!(@bytes, out, key, value, OmObjectClass);
         true
     } else if key == "userParameters" {
-        output_as_struct!(key, value, UserParameters);
+        output_as_struct!(out, key, value, UserParameters);
         true
     } else {
         false
@@ -652,23 +756,56 @@ pub(crate) fn output_special_binary_value(key: &str, value: &[u8]) -> bool {
 }
 
 
-pub(crate) fn output_values(key: &str, values: &[LdapValue], object_classes: &[LdapValue]) {
-    for value in values {
-        match value {
-            LdapValue::Binary(bin_value) => {
-                if !output_special_binary_value(key, bin_value) {
-                    output_binary_value_as_hexdump(key, bin_value);
-                }
-            },
-            LdapValue::String(str_value) => {
-                if !output_special_string_value(key, str_value, object_classes) {
-                    // maybe a binary value was heuristically misdetected as a string
-                    if !output_special_binary_value(key, str_value.as_bytes()) {
-                        output_string_value_as_string(key, str_value);
-                    }
+pub fn output_value<O: ValueOutput>(out: &mut O, key: &str, value: &LdapValue, object_classes: &[LdapValue]) {
+    match value {
+        LdapValue::Binary(bin_value) => {
+            if !output_special_binary_value(out, key, bin_value) {
+                out.binary(key, bin_value);
+            }
+        },
+        LdapValue::String(str_value) => {
+            if !output_special_string_value(out, key, str_value, object_classes) {
+                // maybe a binary value was heuristically misdetected as a string
+                if !output_special_binary_value(out, key, str_value.as_bytes()) {
+                    out.string(key, str_value);
                 }
-            },
-        }
+            }
+        },
+    }
+}
+
+
+/// Collects the SIDs within a value that are output along with their account names, so that these
+/// can be resolved before the value is output.
+pub fn sids_in_value(key: &str, value: &LdapValue) -> Vec<Sid> {
+    let value_bytes = match value {
+        LdapValue::String(s) => s.as_bytes(),
+        LdapValue::Binary(b) => b.as_slice(),
+    };
+    if SID_ATTRIBUTES.contains(&key) {
+        Sid::try_from_bytes(value_bytes).into_iter().collect()
+    } else if key == "msDS-Cached-Membership" {
+        CachedMembership::try_from_bytes(value_bytes)
+            .map(|cm| cm.sids().into_iter().cloned().collect())
+            .unwrap_or_default()
+    } else if key == "msDS-TrustForestTrustInfo" {
+        TrustForestTrustInfo::try_from_bytes(value_bytes)
+            .map(|tfti| tfti.sids().into_iter().cloned().collect())
+            .unwrap_or_default()
+    } else if SECURITY_DESCRIPTOR_ATTRIBUTES.contains(&key) {
+        SecurityDescriptor::try_from_bytes(value_bytes)
+            .map(|sd| sd.sids().into_iter().cloned().collect())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+
+pub fn output_values(key: &str, values: &[LdapValue], object_classes: &[LdapValue], sid_names: Option<&SidNames>) {
+    let mut out = TextOutput { sid_names };
+    for value in values {
+        output_value(&mut out, key, value, object_classes);
     }
 }
 
@@ -3,12 +3,12 @@ use serde::{Deserialize, Serialize};
 
 bitflags! {
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct AddressBookFlags : i32 {
+    pub struct AddressBookFlags : i32 {
         const SHOW_GAL_AS_DEFAULT_VIEW = 0x0000_0001;
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct ProvisioningFlags : u32 {
+    pub struct ProvisioningFlags : u32 {
         const RESERVED_FLAG = 0x0001;
         const EXCLUDED_FROM_PROVISIONING = 0x0002;
         const SUSPENDED_FROM_PROVISIONING = 0x0004;
@@ -23,7 +23,7 @@ bitflags! {
 
     // https://learn.microsoft.com/en-us/powershell/exchange/recipientfilter-properties
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct ElcMailboxFlags : u32 {
+    pub struct ElcMailboxFlags : u32 {
         const EXPIRATION_SUSPENDED = 0x0001;
         const ELC_V2 = 0x0002;
         const DISABLE_CALENDAR_LOGGING = 0x0004;
@@ -38,7 +38,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct SoftDeletedStatus : u32 {
+    pub struct SoftDeletedStatus : u32 {
         const REMOVED = 0x0001;
         const DISABLED = 0x0002;
         const INCLUDE_IN_GARBAGE_COLLECTION = 0x0004;
@@ -46,7 +46,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct RecipientTypeDetails : i64 {
+    pub struct RecipientTypeDetails : i64 {
         const USER_MAILBOX = 0x0000_0000_0000_0001;
         const LINKED_MAILBOX = 0x0000_0000_0000_0002;
         const SHARED_MAILBOX = 0x0000_0000_0000_0004;
@@ -99,7 +99,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct ModerationFlags : i32 {
+    pub struct ModerationFlags : i32 {
         /// Notify senders in the organization when their messages are rejected.
         const NOTIFY_INTERNAL = 0x0000_0002;
 
@@ -108,7 +108,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct TransportSettingFlags : i32 {
+    pub struct TransportSettingFlags : i32 {
         const MESSAGE_TRACKING_READ_STATUS_DISABLED = 0x0000_0004;
         const INTERNAL_ONLY = 0x0000_0008;
         const OPEN_DOMAIN_ROUTING_DISABLED = 0x0000_0010;
@@ -119,18 +119,18 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct MobileMailboxFlags : i32 {
+    pub struct MobileMailboxFlags : i32 {
         const HAS_DEVICE_PARTNERSHIP = 0x0000_0001;
         const ACTIVE_SYNC_SUPPRESS_READ_RECEIPT = 0x0000_0002;
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct LocalizationFlags : i32 {
+    pub struct LocalizationFlags : i32 {
         const LOCALIZATION_DISABLED = 0x0000_0001;
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct MailboxFolderSet : i32 {
+    pub struct MailboxFolderSet : i32 {
         const GLOBAL_ADDRESS_LIST_ENABLED = 0x0000_0001;
         const CALENDAR_ENABLED = 0x0000_0002;
         const CONTACTS_ENABLED = 0x0000_0004;
@@ -166,7 +166,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct MailboxAuditOperations : i32 {
+    pub struct MailboxAuditOperations : i32 {
         const UPDATE = 0x0000_0001;
         const COPY = 0x0000_0002;
         const MOVE = 0x0000_0004;
@@ -188,7 +188,7 @@ bitflags! {
     }
 
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct DeviceClientType : i32 {
+    pub struct DeviceClientType : i32 {
         const EAS = 0x0000_0001;
         const MOWA = 0x0000_0002;
         const OUTLOOK = 0x0000_0004;
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/b9475e91-f00f-4c25-9117-a48e70584625
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum Rid {
+pub enum Rid {
     GroupRidAdmins = 0x0200,
     GroupRidUsers = 0x0201,
     GroupRidGuests = 0x0202,
@@ -52,7 +52,7 @@ pub(crate) enum Rid {
 // https://learn.microsoft.com/en-us/windows/win32/adschema/a-samaccounttype
 #[derive(Clone, Copy, Debug, Deserialize, Eq, FromToRepr, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 #[repr(u32)]
-pub(crate) enum SamAccountType {
+pub enum SamAccountType {
     DomainObject = 0x0,
     GroupObject = 0x10000000,
     NonSecurityGroupObject = 0x10000001,
@@ -70,7 +70,7 @@ pub(crate) enum SamAccountType {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/d49624d0-9320-4368-8b0c-a7998ac2abdb
 #[derive(Clone, Copy, Debug, Deserialize, Eq, FromToRepr, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 #[repr(u32)]
-pub(crate) enum FunctionalityLevel {
+pub enum FunctionalityLevel {
     Win2000 = 0,
     Win2003Mixed = 1,
     Win2003 = 2,
@@ -84,7 +84,7 @@ pub(crate) enum FunctionalityLevel {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/36565693-b5e4-4f37-b0a8-c1b12138e18e
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum TrustType {
+pub enum TrustType {
     Downlevel = 0x00000001,
     Uplevel = 0x00000002,
     Mit = 0x00000003,
@@ -96,7 +96,7 @@ pub(crate) enum TrustType {
 // https://learn.microsoft.com/en-us/windows/win32/ad/structural-abstract-and-auxiliary-classes
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum ObjectClassCategory {
+pub enum ObjectClassCategory {
     Structural = 1,
     Abstract = 2,
     Auxiliary = 3,
@@ -107,7 +107,7 @@ pub(crate) enum ObjectClassCategory {
 // mostly matches ASN.1 tags
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum OmSyntax {
+pub enum OmSyntax {
     Boolean = 1,
     Integer = 2,
     BitString = 3,
@@ -142,7 +142,7 @@ pub(crate) enum OmSyntax {
 // https://learn.microsoft.com/en-us/windows/win32/adschema/a-msds-replauthenticationmode
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum ReplAuthenticationMode {
+pub enum ReplAuthenticationMode {
     NegotiatePassThrough = 0x00000001,
     Negotiate = 0x00000002,
     MutualAuthRequired = 0x00000003,
@@ -151,7 +151,7 @@ pub(crate) enum ReplAuthenticationMode {
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/7cda533e-d7a4-4aec-a517-91d02ff4a1aa
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) enum AttributeSyntax {
+pub enum AttributeSyntax {
     DistinguishedName,
     ObjectIdentifier,
     StringCase,
@@ -224,7 +224,7 @@ impl AttributeSyntax {
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/7cda533e-d7a4-4aec-a517-91d02ff4a1aa
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) enum OmObjectClass {
+pub enum OmObjectClass {
     OrName,
     AccessPoint,
     Other(Vec<u8>),
@@ -255,7 +255,7 @@ impl OmObjectClass {
 // https://learn.microsoft.com/en-us/windows/win32/adschema/a-serverstate
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum ServerState {
+pub enum ServerState {
     Enabled = 1,
     Disabled = 2,
     Other(u32),
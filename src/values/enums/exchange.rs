@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum CapabilityIdentifier {
+pub enum CapabilityIdentifier {
     BposSDeskless = 1,
     BposSStandard = 2,
     BposSEnterprise = 3,
@@ -63,7 +63,7 @@ pub(crate) enum CapabilityIdentifier {
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = i32, derive_compare = "as_int")]
-pub(crate) enum RecipientDisplayType {
+pub enum RecipientDisplayType {
     MailboxUser = 0x0000_0000,
     DistributionGroup = 0x0000_0001,
     PublicFolder = 0x0000_0002,
@@ -111,7 +111,7 @@ pub(crate) enum RecipientDisplayType {
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = i32, derive_compare = "as_int")]
-pub(crate) enum RoleGroupType {
+pub enum RoleGroupType {
     OrganizationManagement = 1,
     RecipientManagement = 2,
     ViewOnlyOrganizationManagement = 3,
@@ -207,7 +207,7 @@ pub(crate) enum RoleGroupType {
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum GroupJoinLeaveRestriction {
+pub enum GroupJoinLeaveRestriction {
     Closed = 0,
     Open = 1,
     ApprovalRequired = 2,
@@ -216,7 +216,7 @@ pub(crate) enum GroupJoinLeaveRestriction {
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum DeletedItemFlags {
+pub enum DeletedItemFlags {
     DatabaseDefault = 0,
     RetainUntilBackupOrCustomPeriod = 3,
     RetainForCustomPeriod = 5,
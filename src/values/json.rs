@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use base64::prelude::{BASE64_STANDARD, Engine};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::values::{LdapValue, output_value, sids_in_value, ValueOutput};
+use crate::values::structs::security::SidNames;
+
+
+/// Collects the decoded form of a single value as JSON.
+#[derive(Debug, Default)]
+pub struct JsonValueCollector {
+    pub decoded: Option<Value>,
+
+    /// The error encountered while converting the decoded value into JSON, if any.
+    pub error: Option<serde_json::Error>,
+}
+impl JsonValueCollector {
+    fn collect<T: Serialize + ?Sized>(&mut self, decoded: &T) {
+        match serde_json::to_value(decoded) {
+            Ok(value) => {
+                self.decoded = Some(value);
+                self.error = None;
+            },
+            Err(e) => {
+                self.decoded = None;
+                self.error = Some(e);
+            },
+        }
+    }
+}
+impl ValueOutput for JsonValueCollector {
+    fn string(&mut self, _key: &str, _value: &str) {
+        self.decoded = None;
+    }
+
+    fn binary(&mut self, _key: &str, _value: &[u8]) {
+        self.decoded = None;
+    }
+
+    fn annotated<T: Serialize + ?Sized>(&mut self, _key: &str, _value: &str, _annotation: &str, decoded: &T) {
+        self.collect(decoded);
+    }
+
+    fn replaced<T: Serialize + ?Sized>(&mut self, _key: &str, _text: &str, decoded: &T) {
+        self.collect(decoded);
+    }
+
+    fn block<T: Serialize + ?Sized>(&mut self, _key: &str, _text: &str, decoded: &T) {
+        self.collect(decoded);
+    }
+
+    fn sid_names(&self) -> Option<&SidNames> {
+        // account names are collected separately by value_to_json
+        None
+    }
+}
+
+
+/// Converts a single attribute value into a JSON object.
+///
+/// The raw value is stored under `value` (for strings) or `base64` (for binary values). If the
+/// value could be decoded, the decoded value is stored under `decoded`. If account names of SIDs
+/// are given, the names of the SIDs within the value are stored under `names`, keyed by SID.
+///
+/// Fails if the decoded value cannot be converted into JSON.
+pub fn value_to_json(key: &str, value: &LdapValue, object_classes: &[LdapValue], sid_names: Option<&SidNames>) -> Result<Value, serde_json::Error> {
+    let mut object = Map::new();
+    match value {
+        LdapValue::String(s) => {
+            object.insert("value".to_owned(), Value::String(s.clone()));
+        },
+        LdapValue::Binary(b) => {
+            object.insert("base64".to_owned(), Value::String(BASE64_STANDARD.encode(b)));
+        },
+    }
+
+    let mut collector = JsonValueCollector::default();
+    output_value(&mut collector, key, value, object_classes);
+    if let Some(error) = collector.error {
+        return Err(error);
+    }
+    if let Some(decoded) = collector.decoded {
+        object.insert("decoded".to_owned(), decoded);
+    }
+
+    if let Some(sid_names) = sid_names {
+        let mut names = Map::new();
+        for sid in sids_in_value(key, value) {
+            if let Some(name) = sid_names.get(&sid) {
+                names.insert(sid.to_string(), Value::String(name.clone()));
+            }
+        }
+        if !names.is_empty() {
+            object.insert("names".to_owned(), Value::Object(names));
+        }
+    }
+
+    Ok(Value::Object(object))
+}
+
+
+/// Converts a directory entry into a JSON object with the keys `dn` and `attributes`.
+///
+/// Fails if a decoded value cannot be converted into JSON.
+pub fn entry_to_json(dn: &str, attributes: &BTreeMap<String, Vec<LdapValue>>, sid_names: Option<&SidNames>) -> Result<Value, serde_json::Error> {
+    let object_classes = attributes
+        .get("objectClass")
+        .map(|oc| oc.as_slice())
+        .unwrap_or(&[]);
+
+    let mut attributes_object = Map::new();
+    for (key, values) in attributes {
+        let json_values = values.iter()
+            .map(|v| value_to_json(key, v, object_classes, sid_names))
+            .collect::<Result<_, _>>()?;
+        attributes_object.insert(key.clone(), Value::Array(json_values));
+    }
+
+    let mut entry_object = Map::new();
+    entry_object.insert("dn".to_owned(), Value::String(dn.to_owned()));
+    entry_object.insert("attributes".to_owned(), Value::Object(attributes_object));
+    Ok(Value::Object(entry_object))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::value_to_json;
+    use crate::values::LdapValue;
+    use crate::values::structs::security::{Sid, SidNames};
+    use serde_json::json;
+
+    #[test]
+    fn test_value_to_json() {
+        let plain = value_to_json("cn", &LdapValue::String("Administrator".to_owned()), &[], None).unwrap();
+        assert_eq!(plain, json!({"value": "Administrator"}));
+
+        let sid_bytes = vec![
+            0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0xF4, 0x01, 0x00, 0x00,
+        ];
+        let sid = value_to_json("objectSid", &LdapValue::Binary(sid_bytes.clone()), &[], None).unwrap();
+        assert_eq!(sid, json!({
+            "base64": "AQUAAAAAAAUVAAAAAQAAAAIAAAADAAAA9AEAAA==",
+            "decoded": "S-1-5-21-1-2-3-500",
+        }));
+
+        let mut sid_names = SidNames::new();
+        sid_names.insert(Sid::try_from_bytes(&sid_bytes).unwrap(), "EXAMPLE\\Administrator".to_owned());
+        let named_sid = value_to_json("objectSid", &LdapValue::Binary(sid_bytes), &[], Some(&sid_names)).unwrap();
+        assert_eq!(named_sid, json!({
+            "base64": "AQUAAAAAAAUVAAAAAQAAAAIAAAADAAAA9AEAAA==",
+            "decoded": "S-1-5-21-1-2-3-500",
+            "names": {"S-1-5-21-1-2-3-500": "EXAMPLE\\Administrator"},
+        }));
+
+        let uac = value_to_json("userAccountControl", &LdapValue::String("512".to_owned()), &[], None).unwrap();
+        assert_eq!(uac, json!({"value": "512", "decoded": "NORMAL_ACCOUNT"}));
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use base64::prelude::{BASE64_STANDARD, Engine};
+use serde::Serialize;
+
+use crate::values::{LdapValue, output_value, ValueOutput};
+use crate::values::structs::security::SidNames;
+
+
+/// The maximum length of a line in LDIF output before it is folded.
+const MAX_LINE_LENGTH: usize = 76;
+
+
+/// Collects the decoded form of a single value as text, for output as an LDIF comment.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LdifCommentCollector<'a> {
+    pub comment: Option<String>,
+    pub sid_names: Option<&'a SidNames>,
+}
+impl<'a> ValueOutput for LdifCommentCollector<'a> {
+    fn string(&mut self, _key: &str, _value: &str) {
+        self.comment = None;
+    }
+
+    fn binary(&mut self, _key: &str, _value: &[u8]) {
+        self.comment = None;
+    }
+
+    fn annotated<T: Serialize + ?Sized>(&mut self, _key: &str, _value: &str, annotation: &str, _decoded: &T) {
+        self.comment = Some(annotation.to_owned());
+    }
+
+    fn replaced<T: Serialize + ?Sized>(&mut self, _key: &str, text: &str, _decoded: &T) {
+        self.comment = Some(text.to_owned());
+    }
+
+    fn block<T: Serialize + ?Sized>(&mut self, _key: &str, text: &str, _decoded: &T) {
+        self.comment = Some(text.to_owned());
+    }
+
+    fn sid_names(&self) -> Option<&SidNames> {
+        self.sid_names
+    }
+}
+
+
+/// Checks whether the given value can be output as a SAFE-STRING as defined by RFC 2849.
+///
+/// Values that are not safe strings must be output in base64 encoding.
+pub fn is_safe_ldif_string(value: &[u8]) -> bool {
+    let Some(first_byte) = value.first() else {
+        // empty strings are safe
+        return true;
+    };
+    if *first_byte == b' ' || *first_byte == b':' || *first_byte == b'<' {
+        // unsafe first character
+        return false;
+    }
+    if value.last() == Some(&b' ') {
+        // RFC 2849 recommends base64 encoding for values ending in a space
+        return false;
+    }
+    // only non-NUL, non-CR, non-LF ASCII is safe
+    value.iter()
+        .all(|b| *b != b'\0' && *b != b'\n' && *b != b'\r' && *b < 0x80)
+}
+
+
+/// Folds a line according to RFC 2849, ensuring no line exceeds the maximum line length.
+///
+/// Lines are only broken at character boundaries. Each continuation line begins with a space.
+pub fn fold_ldif_line(line: &str) -> String {
+    let mut ret = String::with_capacity(line.len());
+    let mut current_line_length = 0;
+    for c in line.chars() {
+        if current_line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            ret.push_str("\n ");
+            current_line_length = 1;
+        }
+        ret.push(c);
+        current_line_length += c.len_utf8();
+    }
+    ret
+}
+
+
+/// Formats an attribute-value pair as an LDIF line, encoding the value in base64 if necessary.
+pub fn ldif_attribute_line(key: &str, value: &[u8]) -> String {
+    let line = if is_safe_ldif_string(value) {
+        // safe strings are ASCII, so this cannot fail
+        format!("{}: {}", key, std::str::from_utf8(value).unwrap())
+    } else {
+        format!("{}:: {}", key, BASE64_STANDARD.encode(value))
+    };
+    fold_ldif_line(&line)
+}
+
+
+/// Formats a directory entry as an LDIF record.
+///
+/// If `decoded_comments` is true, each value that can be decoded is followed by comment lines
+/// containing its decoded form, which includes the account names in `sid_names`. The returned
+/// string does not contain the blank line that separates records.
+pub fn entry_to_ldif(
+    dn: &str,
+    attributes: &BTreeMap<String, Vec<LdapValue>>,
+    decoded_comments: bool,
+    sid_names: Option<&SidNames>,
+) -> String {
+    let object_classes = attributes
+        .get("objectClass")
+        .map(|oc| oc.as_slice())
+        .unwrap_or(&[]);
+
+    let mut ret = ldif_attribute_line("dn", dn.as_bytes());
+    ret.push('\n');
+    for (key, values) in attributes {
+        for value in values {
+            ret.push_str(&value_to_ldif(key, value, object_classes, decoded_comments, sid_names));
+        }
+    }
+    ret
+}
+
+
+/// Formats a single attribute value as LDIF.
+///
+/// If `decoded_comment` is true and the value can be decoded, the value is followed by comment
+/// lines containing its decoded form, which includes the account names in `sid_names`. The
+/// returned string ends with a newline.
+pub fn value_to_ldif(
+    key: &str,
+    value: &LdapValue,
+    object_classes: &[LdapValue],
+    decoded_comment: bool,
+    sid_names: Option<&SidNames>,
+) -> String {
+    let value_bytes = match value {
+        LdapValue::String(s) => s.as_bytes(),
+        LdapValue::Binary(b) => b.as_slice(),
+    };
+    let mut ret = ldif_attribute_line(key, value_bytes);
+    ret.push('\n');
+
+    if !decoded_comment {
+        return ret;
+    }
+    let mut collector = LdifCommentCollector {
+        comment: None,
+        sid_names,
+    };
+    output_value(&mut collector, key, value, object_classes);
+    if let Some(comment) = collector.comment {
+        let comment = comment.replace("\r\n", "\n").replace("\r", "\n");
+        for comment_line in comment.split("\n") {
+            ret.push_str(&fold_ldif_line(&format!("# {}", comment_line)));
+            ret.push('\n');
+        }
+    }
+    ret
+}
+
+
+/// An error encountered while parsing an LDIF file.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LdifParseError {
+    /// The (one-based) number of the line at which the erroneous logical line starts.
+    pub line: usize,
+    pub message: &'static str,
+}
+impl fmt::Display for LdifParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl std::error::Error for LdifParseError {
+}
+
+
+/// A directory entry read from an LDIF file.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LdifRecord {
+    pub dn: String,
+    pub attributes: BTreeMap<String, Vec<LdapValue>>,
+}
+
+
+/// Splits LDIF text into logical lines, undoing line folding and removing comments.
+///
+/// Each logical line is returned along with the number of the physical line at which it starts.
+/// Empty lines are retained since they separate records.
+fn unfold_ldif_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut in_comment = false;
+    for (i, physical_line) in text.split('\n').enumerate() {
+        let physical_line = physical_line.strip_suffix('\r').unwrap_or(physical_line);
+        if let Some(continuation) = physical_line.strip_prefix(' ') {
+            if in_comment {
+                // continuation of a comment
+                continue;
+            }
+            if let Some((_, last_line)) = lines.last_mut() {
+                if !last_line.is_empty() {
+                    last_line.push_str(continuation);
+                    continue;
+                }
+            }
+            // a continuation without a line to continue; treat it as a line of its own
+        }
+
+        in_comment = physical_line.starts_with('#');
+        if in_comment {
+            continue;
+        }
+        lines.push((i + 1, physical_line.to_owned()));
+    }
+    lines
+}
+
+
+/// Parses a single attribute-value line into the attribute name and the raw value.
+fn parse_ldif_attribute_line(line_number: usize, line: &str) -> Result<(String, Vec<u8>), LdifParseError> {
+    let error = |message| LdifParseError { line: line_number, message };
+
+    let Some((key, rest)) = line.split_once(':') else {
+        return Err(error("missing colon after attribute name"));
+    };
+    if key.is_empty() {
+        return Err(error("empty attribute name"));
+    }
+
+    let value = if let Some(base64_value) = rest.strip_prefix(':') {
+        BASE64_STANDARD.decode(base64_value.trim_start_matches(' '))
+            .map_err(|_| error("invalid base64 value"))?
+    } else if rest.starts_with('<') {
+        return Err(error("values referenced by URL are not supported"));
+    } else {
+        rest.trim_start_matches(' ').as_bytes().to_vec()
+    };
+    Ok((key.to_owned(), value))
+}
+
+
+/// Parses the content records of an LDIF file.
+///
+/// Comments and line folding are handled as specified by RFC 2849. The version line is skipped, as
+/// are records without a DN (such as the search result summary output by OpenLDAP's `ldapsearch`)
+/// and `changetype: add` lines (as output by `ldifde`). Other change records are rejected.
+///
+/// As with values returned by an LDAP server, the values of an attribute are returned as strings if
+/// all of them are valid UTF-8 and as binary values otherwise.
+pub fn parse_ldif(text: &str) -> Result<Vec<LdifRecord>, LdifParseError> {
+    let mut records = Vec::new();
+    let mut current_dn: Option<String> = None;
+    let mut current_raw_attributes: BTreeMap<String, Vec<Vec<u8>>> = BTreeMap::new();
+    let mut skipping_record = false;
+    let mut is_first_line = true;
+
+    let mut lines = unfold_ldif_lines(text);
+    // add an empty line to ensure the final record is finished
+    lines.push((0, String::new()));
+
+    for (line_number, line) in lines {
+        if line.is_empty() {
+            // end of record
+            if let Some(dn) = current_dn.take() {
+                let attributes = raw_attributes_to_values(std::mem::take(&mut current_raw_attributes));
+                records.push(LdifRecord {
+                    dn,
+                    attributes,
+                });
+            }
+            current_raw_attributes.clear();
+            skipping_record = false;
+            continue;
+        }
+        if skipping_record {
+            continue;
+        }
+
+        let (key, value) = parse_ldif_attribute_line(line_number, &line)?;
+        let was_first_line = is_first_line;
+        is_first_line = false;
+
+        if current_dn.is_none() {
+            if was_first_line && key == "version" {
+                // version line; may be followed by a record without an empty line in between
+                continue;
+            }
+            if !key.eq_ignore_ascii_case("dn") {
+                // not a directory entry
+                skipping_record = true;
+                continue;
+            }
+            let dn = String::from_utf8(value)
+                .map_err(|_| LdifParseError { line: line_number, message: "DN is not valid UTF-8" })?;
+            current_dn = Some(dn);
+            continue;
+        }
+
+        if key.eq_ignore_ascii_case("changetype") {
+            if value.eq_ignore_ascii_case(b"add") {
+                continue;
+            }
+            return Err(LdifParseError { line: line_number, message: "only content records and add records are supported" });
+        }
+        if key.eq_ignore_ascii_case("control") {
+            return Err(LdifParseError { line: line_number, message: "change records with controls are not supported" });
+        }
+
+        current_raw_attributes
+            .entry(key)
+            .or_default()
+            .push(value);
+    }
+    Ok(records)
+}
+
+
+fn raw_attributes_to_values(raw_attributes: BTreeMap<String, Vec<Vec<u8>>>) -> BTreeMap<String, Vec<LdapValue>> {
+    let mut attributes = BTreeMap::new();
+    for (key, raw_values) in raw_attributes {
+        let all_utf8 = raw_values.iter()
+            .all(|v| std::str::from_utf8(v).is_ok());
+        let values = if all_utf8 {
+            raw_values.into_iter()
+                .map(|v| LdapValue::String(String::from_utf8(v).unwrap()))
+                .collect()
+        } else {
+            raw_values.into_iter()
+                .map(LdapValue::Binary)
+                .collect()
+        };
+        attributes.insert(key, values);
+    }
+    attributes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{entry_to_ldif, fold_ldif_line, is_safe_ldif_string, parse_ldif};
+    use crate::values::LdapValue;
+    use crate::values::structs::security::{Sid, SidNames};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_safe_string() {
+        assert!(is_safe_ldif_string(b""));
+        assert!(is_safe_ldif_string(b"cn=Administrator,cn=Users,dc=example,dc=com"));
+        assert!(!is_safe_ldif_string(b" leading space"));
+        assert!(!is_safe_ldif_string(b"trailing space "));
+        assert!(!is_safe_ldif_string(b":colon"));
+        assert!(!is_safe_ldif_string(b"<angle"));
+        assert!(!is_safe_ldif_string(b"line\nbreak"));
+        assert!(!is_safe_ldif_string("M\u{FC}ller".as_bytes()));
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = "a".repeat(80);
+        let folded = fold_ldif_line(&line);
+        assert_eq!(folded, format!("{}\n {}", "a".repeat(76), "a".repeat(4)));
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut attributes = BTreeMap::new();
+        attributes.insert(
+            "objectSid".to_owned(),
+            vec![LdapValue::Binary(vec![
+                0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x12, 0x00, 0x00, 0x00,
+            ])],
+        );
+        attributes.insert("cn".to_owned(), vec![LdapValue::String("SYSTEM".to_owned())]);
+        let ldif = entry_to_ldif("cn=SYSTEM,dc=example,dc=com", &attributes, true, None);
+        assert_eq!(ldif, concat!(
+            "dn: cn=SYSTEM,dc=example,dc=com\n",
+            "cn: SYSTEM\n",
+            "objectSid:: AQEAAAAAAAUSAAAA\n",
+            "# S-1-5-18\n",
+        ));
+
+        let mut sid_names = SidNames::new();
+        sid_names.insert(Sid::from_sddl("SY").unwrap(), "NT AUTHORITY\\SYSTEM".to_owned());
+        let ldif = entry_to_ldif("cn=SYSTEM,dc=example,dc=com", &attributes, true, Some(&sid_names));
+        assert!(ldif.ends_with("# S-1-5-18 (NT AUTHORITY\\SYSTEM)\n"));
+    }
+
+    #[test]
+    fn test_parse() {
+        const LDIF: &str = concat!(
+            "# extended LDIF\n",
+            "version: 1\n",
+            "dn: cn=SYSTEM,dc=exa\n",
+            " mple,dc=com\n",
+            "changetype: add\n",
+            "cn: SYSTEM\n",
+            "# a comment\n",
+            "  that continues\n",
+            "objectSid:: AQUAAAAAAAUVAAAAAQA\n",
+            " AAAIAAAADAAAA9AEAAA==\n",
+            "description:: TcO8bGxlcg==\n",
+            "\n",
+            "# search result\n",
+            "search: 2\n",
+            "result: 0 Success\n",
+        );
+        let records = parse_ldif(LDIF).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].dn, "cn=SYSTEM,dc=example,dc=com");
+        assert_eq!(records[0].attributes.len(), 3);
+        assert_eq!(records[0].attributes["cn"], vec![LdapValue::String("SYSTEM".to_owned())]);
+        assert_eq!(records[0].attributes["description"], vec![LdapValue::String("M\u{FC}ller".to_owned())]);
+        assert_eq!(
+            records[0].attributes["objectSid"],
+            vec![LdapValue::Binary(vec![
+                0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0xF4, 0x01, 0x00, 0x00,
+            ])],
+        );
+
+        let round_tripped = parse_ldif(&entry_to_ldif(&records[0].dn, &records[0].attributes, true, None)).unwrap();
+        assert_eq!(round_tripped, records);
+    }
+}
@@ -1,8 +1,8 @@
-pub(crate) mod dfsr;
-pub(crate) mod dns;
-pub(crate) mod exchange;
-pub(crate) mod replication;
-pub(crate) mod schema;
-pub(crate) mod security;
-pub(crate) mod terminal_services;
-pub(crate) mod trust;
+pub mod dfsr;
+pub mod dns;
+pub mod exchange;
+pub mod replication;
+pub mod schema;
+pub mod security;
+pub mod terminal_services;
+pub mod trust;
@@ -1,2 +1,2 @@
-pub(crate) mod property;
-pub(crate) mod record;
+pub mod property;
+pub mod record;
@@ -11,7 +11,7 @@ use crate::values::utc_seconds_relative_to_1601;
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/445c7843-e4a1-4222-8c0f-630c230a4c80
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct DnsProperty {
+pub struct DnsProperty {
     // data_length: u32,
     pub name_length: u32,
     pub flag: u32,
@@ -246,7 +246,7 @@ impl DnsProperty {
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/3af63871-0cc4-4179-916c-5caade55a8f3
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) enum DnsPropertyData {
+pub enum DnsPropertyData {
     ZoneType(ZoneType),
     AllowUpdate(AllowUpdate),
     SecureTime(DateTime<Utc>),
@@ -272,7 +272,7 @@ pub(crate) enum DnsPropertyData {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/27e138a7-110c-44a4-afcb-b95f35f00306
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum ZoneType {
+pub enum ZoneType {
     Cache = 0x00,
     Primary = 0x01,
     Secondary = 0x02,
@@ -285,7 +285,7 @@ pub(crate) enum ZoneType {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/e8651544-0fbb-4038-8232-375ff2d8a55e fAllowUpdate
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum AllowUpdate {
+pub enum AllowUpdate {
     Off = 0x00,
     Unsecure = 0x01,
     Secure = 0x02,
@@ -294,7 +294,7 @@ pub(crate) enum AllowUpdate {
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum Boolean {
+pub enum Boolean {
     False = 0,
     True = 1,
     Other(u32),
@@ -309,7 +309,7 @@ impl From<Boolean> for bool {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/4ec7bdf7-1807-4179-96af-ce1c1cd448b7
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
-pub(crate) enum DcPromoFlag {
+pub enum DcPromoFlag {
     ConvertNone = 0x00,
     ConvertDomain = 0x01,
     ConvertForest = 0x02,
@@ -319,7 +319,7 @@ pub(crate) enum DcPromoFlag {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/f448341f-512d-414a-aaa3-e303d592fcd2
 bitflags! {
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-    pub(crate) struct DnsRpcNodeFlags : u32 {
+    pub struct DnsRpcNodeFlags : u32 {
         const CacheData = 0x80000000;
         const ZoneRoot = 0x40000000;
         const AuthZoneRoot = 0x20000000;
@@ -342,7 +342,7 @@ bitflags! {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/aea3ba9b-de48-4f0e-a4c8-4f7c89404b99
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/6e041c76-3b55-480a-84fb-feebcb0cc9db
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct DnsAddr {
+pub struct DnsAddr {
     pub ip: DnsIpAddr,
     pub port: u16,
     pub subnet_length: u32,
@@ -416,7 +416,7 @@ impl DnsAddr {
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) enum DnsIpAddr {
+pub enum DnsIpAddr {
     V4(Ipv4Addr),
     V6(Ipv6Addr),
     Other {
@@ -428,7 +428,7 @@ pub(crate) enum DnsIpAddr {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dnsp/f9f3901a-862f-4bdb-a7c4-963dae44c13e
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u16, derive_compare = "as_int")]
-pub(crate) enum DnsValidationStatus {
+pub enum DnsValidationStatus {
     Success = 0x0000,
     InvalidAddr = 0x0001,
     Unreachable = 0x0002,
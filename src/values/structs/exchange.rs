@@ -7,7 +7,7 @@ use crate::{bit_is_set, extract_bits};
 
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct TextMessagingState {
+pub struct TextMessagingState {
     pub m2p_priority: u8,
     pub p2p_priority: u8,
     pub identity: u8,
@@ -50,7 +50,7 @@ impl TextMessagingState {
 
 
 #[derive(Clone, Copy, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct ExchangeVersion {
+pub struct ExchangeVersion {
     // do_not_care: u6
     pub major: u8,
     pub minor: u8,
@@ -114,7 +114,7 @@ impl fmt::Debug for ExchangeVersion {
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct InternetEncoding {
+pub struct InternetEncoding {
     // do_not_care: u17
     pub use_preferred_message_format: bool, // u1
     pub message_format: MessageFormat, // u1
@@ -211,7 +211,7 @@ pub enum GroupAccessType {
 
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct MailboxFolderSet2 {
+pub struct MailboxFolderSet2 {
     pub force_save_attachment_filtering_enabled: bool, // u1 (0)
     pub silverlight_enabled: bool, // u1 (1)
     pub anonymous_features_enabled: bool, // u1 (2)
@@ -14,7 +14,7 @@ use crate::values::{
 
 // gleaned from ldp.exe
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct ReplUpToDateVector2 {
+pub struct ReplUpToDateVector2 {
     pub version: u32,
     pub reserved1: u32,
     // num_cursors: u32,
@@ -60,7 +60,7 @@ impl ReplUpToDateVector2 {
 
 // gleaned from ldp.exe
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct Repl2Cursor {
+pub struct Repl2Cursor {
     pub uuid_dsa: Uuid,
     pub usn_high_prop_update: u64,
     pub time_last_sync_success: DateTime<Utc>,
@@ -88,7 +88,7 @@ impl Repl2Cursor {
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-drsr/b422aa87-7d07-4527-b070-c5d719696c43
 // (structures are equivalent)
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct RepsFromTo {
+pub struct RepsFromTo {
     pub version: u32,
     pub reserved0: u32,
     // cb: u32,
@@ -181,7 +181,7 @@ impl RepsFromTo {
 
 
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub(crate) struct ReplTimes {
+pub struct ReplTimes {
     pub times: [u8; 84],
 }
 impl ReplTimes {
@@ -234,7 +234,7 @@ impl Serialize for ReplTimes {
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-drsr/595d11b8-6ca7-4a61-bd56-3e6a2b99b76b
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
-pub(crate) struct UsnVector {
+pub struct UsnVector {
     pub usn_high_obj_update: u64,
     pub usn_reserved: u64,
     pub usn_high_prop_update: u64,
@@ -395,7 +395,7 @@ bitflags! {
 }
 
 
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum DsCorePropagationData {
     Flags(DsCorePropagationFlags),
     Time(DateTime<Utc>),
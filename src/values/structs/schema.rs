@@ -1,9 +1,10 @@
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::oid_prefix::OidPrefix;
 
 
-#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct PrefixMap {
     // num_entries: u32,
     // num_bytes: u32,
@@ -56,14 +57,14 @@ impl PrefixMap {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct PrefixEntry {
     pub db_prefix: u16,
     // ber_prefix_length: u16,
     pub oid_prefix: OidPrefix, // [u8; ber_prefix_length]
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct SchemaInfo {
     pub identifier: u8,
     pub schema_version: u32,
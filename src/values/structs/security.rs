@@ -1,6 +1,9 @@
+pub mod conditional;
+pub mod explain;
 pub mod key_credential_link;
 
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Write as _};
 use std::str::FromStr;
 
@@ -11,6 +14,84 @@ use uuid::Uuid;
 
 use crate::{bit_is_set, extract_bits};
 use crate::values::nul_terminated_utf16le_string;
+use crate::values::structs::security::conditional::{
+    conditional_expression_from_sddl, conditional_expression_to_sddl,
+};
+
+
+// https://learn.microsoft.com/en-us/windows/win32/secauthz/sid-strings
+// https://learn.microsoft.com/en-us/windows/win32/secauthz/well-known-sids
+/// The well-known SIDs with an SDDL alias, as (alias, authority, subauthorities, account name).
+const WELL_KNOWN_SDDL_SIDS: [(&str, u64, &[u32], &str); 47] = [
+    // world authority
+    ("WD", 1, &[0], "Everyone"),
+
+    // creator authority
+    ("CO", 3, &[0], "CREATOR OWNER"),
+    ("CG", 3, &[1], "CREATOR GROUP"),
+    ("OW", 3, &[4], "OWNER RIGHTS"),
+
+    // NT authority
+    ("NU", 5, &[2], "NT AUTHORITY\\NETWORK"),
+    ("IU", 5, &[4], "NT AUTHORITY\\INTERACTIVE"),
+    ("SU", 5, &[6], "NT AUTHORITY\\SERVICE"),
+    ("AN", 5, &[7], "NT AUTHORITY\\ANONYMOUS LOGON"),
+    ("ED", 5, &[9], "NT AUTHORITY\\ENTERPRISE DOMAIN CONTROLLERS"),
+    ("PS", 5, &[10], "NT AUTHORITY\\SELF"),
+    ("AU", 5, &[11], "NT AUTHORITY\\Authenticated Users"),
+    ("RC", 5, &[12], "NT AUTHORITY\\RESTRICTED"),
+    ("SY", 5, &[18], "NT AUTHORITY\\SYSTEM"),
+    ("LS", 5, &[19], "NT AUTHORITY\\LOCAL SERVICE"),
+    ("NS", 5, &[20], "NT AUTHORITY\\NETWORK SERVICE"),
+    ("WR", 5, &[33], "NT AUTHORITY\\WRITE RESTRICTED"),
+
+    // NT authority, built-in domain
+    ("BA", 5, &[32, 544], "BUILTIN\\Administrators"),
+    ("BU", 5, &[32, 545], "BUILTIN\\Users"),
+    ("BG", 5, &[32, 546], "BUILTIN\\Guests"),
+    ("PU", 5, &[32, 547], "BUILTIN\\Power Users"),
+    ("AO", 5, &[32, 548], "BUILTIN\\Account Operators"),
+    ("SO", 5, &[32, 549], "BUILTIN\\Server Operators"),
+    ("PO", 5, &[32, 550], "BUILTIN\\Print Operators"),
+    ("BO", 5, &[32, 551], "BUILTIN\\Backup Operators"),
+    ("RE", 5, &[32, 552], "BUILTIN\\Replicator"),
+    ("RS", 5, &[32, 553], "BUILTIN\\RAS and IAS Servers"),
+    ("RU", 5, &[32, 554], "BUILTIN\\Pre-Windows 2000 Compatible Access"),
+    ("RD", 5, &[32, 555], "BUILTIN\\Remote Desktop Users"),
+    ("NO", 5, &[32, 556], "BUILTIN\\Network Configuration Operators"),
+    ("MU", 5, &[32, 558], "BUILTIN\\Performance Monitor Users"),
+    ("LU", 5, &[32, 559], "BUILTIN\\Performance Log Users"),
+    ("IS", 5, &[32, 568], "BUILTIN\\IIS_IUSRS"),
+    ("CY", 5, &[32, 569], "BUILTIN\\Cryptographic Operators"),
+    ("ER", 5, &[32, 573], "BUILTIN\\Event Log Readers"),
+    ("CD", 5, &[32, 574], "BUILTIN\\Certificate Service DCOM Access"),
+    ("RA", 5, &[32, 575], "BUILTIN\\RDS Remote Access Servers"),
+    ("ES", 5, &[32, 576], "BUILTIN\\RDS Endpoint Servers"),
+    ("HA", 5, &[32, 578], "BUILTIN\\Hyper-V Administrators"),
+    ("AA", 5, &[32, 579], "BUILTIN\\Access Control Assistance Operators"),
+    ("HO", 5, &[32, 584], "BUILTIN\\User Mode Hardware Operators"),
+
+    // application package authority
+    ("AC", 15, &[2, 1], "APPLICATION PACKAGE AUTHORITY\\ALL APPLICATION PACKAGES"),
+
+    // mandatory label authority
+    ("LW", 16, &[4096], "Mandatory Label\\Low Mandatory Level"),
+    ("ME", 16, &[8192], "Mandatory Label\\Medium Mandatory Level"),
+    ("MP", 16, &[8448], "Mandatory Label\\Medium Plus Mandatory Level"),
+    ("HI", 16, &[12288], "Mandatory Label\\High Mandatory Level"),
+    ("SI", 16, &[16384], "Mandatory Label\\System Mandatory Level"),
+
+    // authentication authority
+    ("SS", 18, &[2], "Service asserted identity"),
+];
+
+
+/// The account names of SIDs (e.g. `EXAMPLE\jdoe`), e.g. as looked up in the directory.
+pub type SidNames = BTreeMap<Sid, String>;
+
+
+/// The maximum number of subauthorities in a SID accepted by Windows (`SID_MAX_SUB_AUTHORITIES`).
+pub const SID_MAX_SUB_AUTHORITIES: usize = 15;
 
 
 #[derive(Clone, Debug, Deserialize, Hash, Eq, Ord, PartialEq, PartialOrd, Serialize)]
@@ -78,105 +159,42 @@ impl Sid {
         })
     }
 
-    // https://learn.microsoft.com/en-us/windows/win32/secauthz/sid-strings
-    // https://learn.microsoft.com/en-us/windows/win32/secauthz/well-known-sids
-    pub fn as_well_known_sddl_sid_string(&self) -> Option<&'static str> {
+    /// Serializes the SID into its binary representation.
+    ///
+    /// Returns `None` if the SID has more than 255 subauthorities.
+    pub fn try_to_bytes(&self) -> Option<Vec<u8>> {
+        let subauthority_count = u8::try_from(self.subauthorities.len()).ok()?;
+        let mut ret = Vec::with_capacity(8 + 4*self.subauthorities.len());
+        ret.push(self.version);
+        ret.push(subauthority_count);
+        // big endian, 48 bits
+        ret.extend_from_slice(&self.authority.to_be_bytes()[2..8]);
+        for sub_auth in &self.subauthorities {
+            // little endian
+            ret.extend_from_slice(&sub_auth.to_le_bytes());
+        }
+        Some(ret)
+    }
+
+    /// The entry of this SID in the table of well-known SIDs with an SDDL alias.
+    fn well_known_entry(&self) -> Option<&'static (&'static str, u64, &'static [u32], &'static str)> {
         if self.version != 1 {
             return None;
         }
+        WELL_KNOWN_SDDL_SIDS.iter()
+            .find(|(_alias, authority, subauthorities, _name)| *authority == self.authority && *subauthorities == self.subauthorities.as_slice())
+    }
 
-        match self.authority {
-            1 => match self.subauthorities.len() { // world authority
-                1 => match self.subauthorities[0] {
-                    0 => Some("WD"),
-                    _ => None,
-                },
-                _ => None,
-            },
-            3 => match self.subauthorities.len() { // creator authority
-                1 => match self.subauthorities[0] {
-                    0 => Some("CO"),
-                    1 => Some("CG"),
-                    4 => Some("OW"),
-                    _ => None,
-                },
-                _ => None,
-            },
-            5 => match self.subauthorities.len() { // NT authority
-                1 => match self.subauthorities[0] {
-                    2 => Some("NU"),
-                    4 => Some("IU"),
-                    6 => Some("SU"),
-                    7 => Some("AN"),
-                    9 => Some("ED"),
-                    10 => Some("PS"),
-                    11 => Some("AU"),
-                    12 => Some("RC"),
-                    18 => Some("SY"),
-                    19 => Some("LS"),
-                    20 => Some("NS"),
-                    33 => Some("WR"),
-                    _ => None,
-                },
-                2 => match self.subauthorities[0] {
-                    32 => match self.subauthorities[1] { // built-in domain
-                        544 => Some("BA"),
-                        545 => Some("BU"),
-                        546 => Some("BG"),
-                        547 => Some("PU"),
-                        548 => Some("AO"),
-                        549 => Some("SO"),
-                        550 => Some("PO"),
-                        551 => Some("BO"),
-                        552 => Some("RE"),
-                        553 => Some("RS"),
-                        554 => Some("RU"),
-                        555 => Some("RD"),
-                        556 => Some("NO"),
-                        558 => Some("MU"),
-                        559 => Some("LU"),
-                        568 => Some("IS"),
-                        569 => Some("CY"),
-                        573 => Some("ER"),
-                        574 => Some("CD"),
-                        575 => Some("RA"),
-                        576 => Some("ES"),
-                        578 => Some("HA"),
-                        579 => Some("AA"),
-                        584 => Some("HO"),
-                        _ => None,
-                    },
-                    _ => None,
-                }
-                _ => None,
-            },
-            15 => match self.subauthorities.len() { // application package authority
-                2 => match (self.subauthorities[0], self.subauthorities[1]) {
-                    (2, 1) => Some("AC"),
-                    _ => None,
-                },
-                _ => None,
-            },
-            16 => match self.subauthorities.len() { // mandatory label authority
-                1 => match self.subauthorities[0] {
-                    4096 => Some("LW"),
-                    8192 => Some("ME"),
-                    8448 => Some("MP"),
-                    12288 => Some("HI"),
-                    16384 => Some("SI"),
-                    _ => None,
-                },
-                _ => None,
-            },
-            18 => match self.subauthorities.len() { // authentication authority
-                1 => match self.subauthorities[0] {
-                    2 => Some("SS"),
-                    _ => None,
-                },
-                _ => None,
-            },
-            _ => None,
-        }
+    pub fn as_well_known_sddl_sid_string(&self) -> Option<&'static str> {
+        self.well_known_entry()
+            .map(|(alias, _authority, _subauthorities, _name)| *alias)
+    }
+
+    /// The name of the account if this is a well-known SID with an SDDL alias, e.g.
+    /// `BUILTIN\Administrators`.
+    pub fn well_known_account_name(&self) -> Option<&'static str> {
+        self.well_known_entry()
+            .map(|(_alias, _authority, _subauthorities, name)| *name)
     }
 
     pub fn to_sddl_sid_string(&self) -> String {
@@ -185,6 +203,37 @@ impl Sid {
             None => self.to_string(),
         }
     }
+
+    /// Parses a SID in SDDL, which is either a well-known alias (e.g. `BA`) or a SID string (e.g.
+    /// `S-1-5-32-544`). Aliases relative to the domain (e.g. `DA`) are not supported, as the SID of
+    /// the domain is not known. SIDs with more than [`SID_MAX_SUB_AUTHORITIES`] subauthorities are
+    /// rejected.
+    pub fn from_sddl(s: &str) -> Result<Self, &'static str> {
+        if s.starts_with("S-") {
+            let sid: Self = s.parse()?;
+            if sid.subauthorities.len() > SID_MAX_SUB_AUTHORITIES {
+                return Err("SID has too many subauthorities");
+            }
+            return Ok(sid);
+        }
+        WELL_KNOWN_SDDL_SIDS.iter()
+            .find(|(alias, _authority, _subauthorities, _name)| *alias == s)
+            .map(|(_alias, authority, subauthorities, _name)| Self {
+                version: 1,
+                authority: *authority,
+                subauthorities: subauthorities.to_vec(),
+            })
+            .ok_or("unknown SID alias")
+    }
+
+    /// Outputs the SID string followed by the account name in parentheses if it is known, e.g.
+    /// `S-1-5-21-1-2-3-1104 (EXAMPLE\jdoe)`.
+    pub fn to_string_with_name(&self, sid_names: &SidNames) -> String {
+        match sid_names.get(self) {
+            Some(name) => format!("{} ({})", self, name),
+            None => self.to_string(),
+        }
+    }
 }
 impl fmt::Display for Sid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -244,6 +293,11 @@ impl FromStr for Sid {
 bitflags! {
     #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
     pub struct AccessMask : u32 {
+        const GenericRead = 0x8000_0000;
+        const GenericWrite = 0x4000_0000;
+        const GenericExecute = 0x2000_0000;
+        const GenericAll = 0x1000_0000;
+
         const WriteOwner = 0x0008_0000;
         const WriteDacl = 0x0004_0000;
         const ReadControl = 0x0002_0000;
@@ -421,6 +475,239 @@ impl ClaimSecurityAttribute1 {
             values,
         })
     }
+
+    /// Serializes the claim into its self-relative binary representation.
+    ///
+    /// Returns `None` if the claim is longer than 4 GiB.
+    pub fn try_to_bytes(&self) -> Option<Vec<u8>> {
+        let (value_type, value_count): (u16, usize) = match &self.values {
+            ClaimValues::Int64s(values) => (0x0001, values.len()),
+            ClaimValues::Uint64s(values) => (0x0002, values.len()),
+            ClaimValues::Strings(values) => (0x0003, values.len()),
+            ClaimValues::Sids(values) => (0x0005, values.len()),
+            ClaimValues::Booleans(values) => (0x0006, values.len()),
+            ClaimValues::OctetStrings(values) => (0x0010, values.len()),
+        };
+
+        // header and offset table first, then the name, then the values
+        let mut ret = vec![0u8; 16 + 4*value_count];
+        let name_offset = ret.len();
+        push_nul_terminated_utf16le(&mut ret, &self.name);
+
+        let mut offsets = Vec::with_capacity(value_count);
+        match &self.values {
+            ClaimValues::Int64s(values) => for value in values {
+                offsets.push(ret.len());
+                ret.extend_from_slice(&value.to_le_bytes());
+            },
+            ClaimValues::Uint64s(values) => for value in values {
+                offsets.push(ret.len());
+                ret.extend_from_slice(&value.to_le_bytes());
+            },
+            ClaimValues::Strings(values) => for value in values {
+                offsets.push(ret.len());
+                push_nul_terminated_utf16le(&mut ret, value);
+            },
+            ClaimValues::Sids(values) => for value in values {
+                offsets.push(ret.len());
+                push_nul_terminated_utf16le(&mut ret, &value.to_string());
+            },
+            ClaimValues::Booleans(values) => for value in values {
+                offsets.push(ret.len());
+                ret.extend_from_slice(&u64::from(*value).to_le_bytes());
+            },
+            ClaimValues::OctetStrings(values) => for value in values {
+                offsets.push(ret.len());
+                ret.extend_from_slice(&u32_length(value.len())?.to_le_bytes());
+                ret.extend_from_slice(value);
+            },
+        }
+
+        ret[0..4].copy_from_slice(&u32_length(name_offset)?.to_le_bytes());
+        ret[4..6].copy_from_slice(&value_type.to_le_bytes());
+        ret[6..8].copy_from_slice(&self.reserved.to_le_bytes());
+        ret[8..12].copy_from_slice(&self.flags.bits().to_le_bytes());
+        ret[12..16].copy_from_slice(&u32_length(value_count)?.to_le_bytes());
+        for (i, offset) in offsets.into_iter().enumerate() {
+            ret[16+4*i..16+4*i+4].copy_from_slice(&u32_length(offset)?.to_le_bytes());
+        }
+        Some(ret)
+    }
+
+    /// Outputs the claim as the attribute of an SDDL resource attribute ACE, e.g.
+    /// `"Project",TS,0x0,"Windows","SQL"`.
+    ///
+    /// Returns `None` if the name or a string value contains a double quote, which SDDL cannot
+    /// represent.
+    pub fn to_sddl(&self) -> Option<String> {
+        let mut ret = format!("{},", sddl_string_literal(&self.name)?);
+        let values: Vec<String> = match &self.values {
+            ClaimValues::Int64s(values) => {
+                ret.push_str("TI");
+                values.iter().map(|v| v.to_string()).collect()
+            },
+            ClaimValues::Uint64s(values) => {
+                ret.push_str("TU");
+                values.iter().map(|v| v.to_string()).collect()
+            },
+            ClaimValues::Strings(values) => {
+                ret.push_str("TS");
+                values.iter()
+                    .map(|v| sddl_string_literal(v))
+                    .collect::<Option<_>>()?
+            },
+            ClaimValues::Sids(values) => {
+                ret.push_str("TD");
+                values.iter().map(|v| format!("SID({})", v.to_sddl_sid_string())).collect()
+            },
+            ClaimValues::Booleans(values) => {
+                ret.push_str("TB");
+                values.iter().map(|v| u8::from(*v).to_string()).collect()
+            },
+            ClaimValues::OctetStrings(values) => {
+                ret.push_str("TX");
+                values.iter()
+                    .map(|v| sddl_octet_string_literal(v))
+                    .collect()
+            },
+        };
+        write!(ret, ",0x{:x}", self.flags.bits()).unwrap();
+        for value in values {
+            ret.push(',');
+            ret.push_str(&value);
+        }
+        Some(ret)
+    }
+
+    /// Parses the attribute of an SDDL resource attribute ACE, e.g.
+    /// `"Project",TS,0x0,"Windows","SQL"`.
+    pub fn from_sddl(s: &str) -> Result<Self, &'static str> {
+        let fields: Vec<&str> = split_sddl_top_level(s, ',')
+            .into_iter()
+            .map(|f| f.trim())
+            .collect();
+        if fields.len() < 3 {
+            return Err("resource attribute does not consist of a name, a type and flags");
+        }
+        let name = parse_sddl_string_literal(fields[0])?;
+        let flags = match fields[2].strip_prefix("0x").or_else(|| fields[2].strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => fields[2].parse(),
+        }
+            .map(ClaimFlags::from_bits_retain)
+            .map_err(|_| "invalid resource attribute flags")?;
+        let value_strings = &fields[3..];
+        let values = match fields[1] {
+            "TI" => ClaimValues::Int64s(
+                value_strings.iter()
+                    .map(|v| v.parse().map_err(|_| "invalid signed integer in resource attribute"))
+                    .collect::<Result<_, _>>()?
+            ),
+            "TU" => ClaimValues::Uint64s(
+                value_strings.iter()
+                    .map(|v| v.parse().map_err(|_| "invalid unsigned integer in resource attribute"))
+                    .collect::<Result<_, _>>()?
+            ),
+            "TS" => ClaimValues::Strings(
+                value_strings.iter()
+                    .map(|v| parse_sddl_string_literal(v))
+                    .collect::<Result<_, _>>()?
+            ),
+            "TD" => ClaimValues::Sids(
+                value_strings.iter()
+                    .map(|v| {
+                        v.strip_prefix("SID(")
+                            .and_then(|sid| sid.strip_suffix(')'))
+                            .ok_or("resource attribute SID is not of the form SID(...)")
+                            .and_then(Sid::from_sddl)
+                    })
+                    .collect::<Result<_, _>>()?
+            ),
+            "TB" => ClaimValues::Booleans(
+                value_strings.iter()
+                    .map(|v| match *v {
+                        "0" => Ok(false),
+                        "1" => Ok(true),
+                        _ => Err("invalid boolean in resource attribute"),
+                    })
+                    .collect::<Result<_, _>>()?
+            ),
+            "TX" => ClaimValues::OctetStrings(
+                value_strings.iter()
+                    .map(|v| parse_sddl_octet_string_literal(v))
+                    .collect::<Result<_, _>>()?
+            ),
+            _ => return Err("unknown resource attribute type"),
+        };
+        Ok(Self {
+            name,
+            reserved: 0,
+            flags,
+            values,
+        })
+    }
+}
+
+
+/// Outputs a string in double quotes as in SDDL, or `None` if it contains a double quote, which
+/// cannot be escaped.
+pub(crate) fn sddl_string_literal(value: &str) -> Option<String> {
+    if value.contains('"') {
+        None
+    } else {
+        Some(format!("\"{}\"", value))
+    }
+}
+
+
+/// Outputs an octet string as in SDDL, i.e. as `#` followed by hexadecimal digits.
+pub(crate) fn sddl_octet_string_literal(value: &[u8]) -> String {
+    let mut ret = "#".to_owned();
+    for b in value {
+        write!(ret, "{:02x}", b).unwrap();
+    }
+    ret
+}
+
+
+/// Parses a string in double quotes as in SDDL.
+pub(crate) fn parse_sddl_string_literal(s: &str) -> Result<String, &'static str> {
+    s.strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .filter(|v| !v.contains('"'))
+        .map(|v| v.to_owned())
+        .ok_or("invalid string literal")
+}
+
+
+/// Parses an octet string as in SDDL, i.e. `#` followed by hexadecimal digits.
+pub(crate) fn parse_sddl_octet_string_literal(s: &str) -> Result<Vec<u8>, &'static str> {
+    let hex = s.strip_prefix('#')
+        .ok_or("octet string does not start with #")?;
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err("octet string does not consist of pairs of hexadecimal digits");
+    }
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i+2], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "octet string does not consist of pairs of hexadecimal digits")
+}
+
+
+fn push_nul_terminated_utf16le(bytes: &mut Vec<u8>, value: &str) {
+    for word in value.encode_utf16().chain(std::iter::once(0)) {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+
+fn u32_length(length: usize) -> Option<u32> {
+    u32::try_from(length).ok()
+}
+
+
+fn u16_length(length: usize) -> Option<u16> {
+    u16::try_from(length).ok()
 }
 
 #[derive(Clone, Debug, Deserialize, Hash, Eq, Ord, PartialEq, PartialOrd, Serialize)]
@@ -566,6 +853,28 @@ impl AceData {
             AceData::Other { .. } => None,
         }
     }
+
+    /// The numeric type of the ACE.
+    pub fn ace_type(&self) -> u8 {
+        match self {
+            AceData::AccessAllowed { .. } => 0x00,
+            AceData::AccessDenied { .. } => 0x01,
+            AceData::SystemAudit { .. } => 0x02,
+            AceData::AccessAllowedObject { .. } => 0x05,
+            AceData::AccessDeniedObject { .. } => 0x06,
+            AceData::SystemAuditObject { .. } => 0x07,
+            AceData::AccessAllowedCallback { .. } => 0x09,
+            AceData::AccessDeniedCallback { .. } => 0x0A,
+            AceData::AccessAllowedCallbackObject { .. } => 0x0B,
+            AceData::AccessDeniedCallbackObject { .. } => 0x0C,
+            AceData::SystemAuditCallback { .. } => 0x0D,
+            AceData::SystemAuditCallbackObject { .. } => 0x0F,
+            AceData::SystemMandatoryLabel { .. } => 0x11,
+            AceData::SystemResourceAttribute { .. } => 0x12,
+            AceData::SystemScopedPolicyId { .. } => 0x13,
+            AceData::Other { kind } => *kind,
+        }
+    }
 }
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/628ebb1d-c509-4ea0-a10f-77ef97ca4586
@@ -582,6 +891,174 @@ bitflags! {
     }
 }
 
+// https://learn.microsoft.com/en-us/windows/win32/secauthz/ace-strings
+/// The SDDL codes of the ACE flags, in the order in which they are output.
+const ACE_FLAGS_SDDL: [(&str, AceFlags); 7] = [
+    ("CI", AceFlags::ContainerInherit),
+    ("OI", AceFlags::ObjectInherit),
+    ("NP", AceFlags::NoPropagateInherit),
+    ("IO", AceFlags::InheritOnly),
+    ("ID", AceFlags::Inherited),
+    ("SA", AceFlags::SuccessfulAccessFlag),
+    ("FA", AceFlags::FailedAccessFlag),
+];
+
+/// The SDDL codes of the access rights, in the order in which they are output.
+const ACCESS_MASK_SDDL: [(&str, AccessMask); 17] = [
+    ("GA", AccessMask::GenericAll),
+    ("GR", AccessMask::GenericRead),
+    ("GW", AccessMask::GenericWrite),
+    ("GX", AccessMask::GenericExecute),
+    ("CC", AccessMask::DsCreateChild),
+    ("DC", AccessMask::DsDeleteChild),
+    ("LC", AccessMask::DsListChildren),
+    ("SW", AccessMask::DsSelfWrite),
+    ("RP", AccessMask::DsReadProp),
+    ("WP", AccessMask::DsWriteProp),
+    ("DT", AccessMask::DsDeleteTree),
+    ("LO", AccessMask::DsListObject),
+    ("CR", AccessMask::DsControlAccess),
+    ("SD", AccessMask::Delete),
+    ("RC", AccessMask::ReadControl),
+    ("WD", AccessMask::WriteDacl),
+    ("WO", AccessMask::WriteOwner),
+];
+
+/// The SDDL codes of the mandatory label rights, in the order in which they are output.
+const MANDATORY_MASK_SDDL: [(&str, MandatoryMask); 3] = [
+    ("NR", MandatoryMask::NoReadUp),
+    ("NW", MandatoryMask::NoWriteUp),
+    ("NX", MandatoryMask::NoExecuteUp),
+];
+
+
+/// Parses a sequence of two-letter SDDL codes into flags.
+fn flags_from_sddl<F: bitflags::Flags + Copy>(s: &str, codes: &[(&str, F)]) -> Result<F, &'static str> {
+    if !s.is_ascii() || !s.len().is_multiple_of(2) {
+        return Err("flags do not consist of two-letter codes");
+    }
+    let mut flags = F::empty();
+    for i in (0..s.len()).step_by(2) {
+        let code = &s[i..i+2];
+        let (_code, flag) = codes.iter()
+            .find(|(c, _flag)| *c == code)
+            .ok_or("unknown flag code")?;
+        flags.insert(*flag);
+    }
+    Ok(flags)
+}
+
+
+/// Outputs flags as a sequence of two-letter SDDL codes, failing if they contain an unknown flag.
+fn flags_to_sddl<F: bitflags::Flags + Copy>(flags: F, codes: &[(&str, F)], ret: &mut String) -> Option<()> {
+    let mut remaining = flags;
+    for (code, flag) in codes {
+        if flags.contains(*flag) {
+            ret.push_str(code);
+            remaining.remove(*flag);
+        }
+    }
+    if remaining.is_empty() {
+        Some(())
+    } else {
+        None
+    }
+}
+
+
+/// Outputs an access mask as a sequence of two-letter SDDL codes or, if it contains rights without
+/// a code, as a hexadecimal number.
+fn mask_to_sddl<F: bitflags::Flags<Bits = u32> + Copy>(mask: F, codes: &[(&str, F)], ret: &mut String) {
+    let mut code_string = String::new();
+    if flags_to_sddl(mask, codes, &mut code_string).is_some() {
+        ret.push_str(&code_string);
+    } else {
+        write!(ret, "0x{:x}", mask.bits()).unwrap();
+    }
+}
+
+
+/// Splits SDDL at the given separator, ignoring separators within parentheses and quotes.
+fn split_sddl_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut depth: usize = 0;
+    let mut in_quotes = false;
+    let mut piece_start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 && !in_quotes => {
+                pieces.push(&s[piece_start..i]);
+                piece_start = i + c.len_utf8();
+            },
+            _ => {},
+        }
+    }
+    pieces.push(&s[piece_start..]);
+    pieces
+}
+
+
+/// Splits a sequence of parenthesized SDDL strings, such as the ACEs of an ACL, into the strings
+/// (including their parentheses).
+fn split_sddl_parenthesized(s: &str) -> Result<Vec<&str>, &'static str> {
+    let mut pieces = Vec::new();
+    let mut depth: usize = 0;
+    let mut in_quotes = false;
+    let mut piece_start = 0;
+    for (i, c) in s.char_indices() {
+        if depth == 0 && c != '(' {
+            return Err("expected an opening parenthesis");
+        }
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => {
+                if depth == 0 {
+                    piece_start = i;
+                }
+                depth += 1;
+            },
+            ')' if !in_quotes => {
+                depth -= 1;
+                if depth == 0 {
+                    pieces.push(&s[piece_start..=i]);
+                }
+            },
+            _ => {},
+        }
+    }
+    if depth != 0 {
+        return Err("unbalanced parentheses");
+    }
+    Ok(pieces)
+}
+
+
+/// Parses access rights given either as two-letter SDDL codes or as a hexadecimal number.
+fn mask_from_sddl<F: bitflags::Flags<Bits = u32> + Copy>(s: &str, codes: &[(&str, F)]) -> Result<F, &'static str> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16)
+            .map(F::from_bits_retain)
+            .map_err(|_| "invalid hexadecimal access mask")
+    } else {
+        flags_from_sddl(s, codes)
+    }
+}
+
+
+fn guid_from_sddl(s: &str) -> Result<Option<Uuid>, &'static str> {
+    if s.is_empty() {
+        Ok(None)
+    } else {
+        Uuid::parse_str(s)
+            .map(Some)
+            .map_err(|_| "invalid GUID")
+    }
+}
+
+
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/628ebb1d-c509-4ea0-a10f-77ef97ca4586
 #[derive(Clone, Debug, Deserialize, Hash, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Ace {
@@ -737,12 +1214,162 @@ impl Ace {
             },
         }
     }
+
+    /// Serializes the ACE into its binary representation.
+    ///
+    /// The flags of object ACEs are adjusted to reflect which object types are present.
+    ///
+    /// Returns `None` if the ACE is longer than 65535 bytes or its SID cannot be serialized.
+    pub fn try_to_bytes(&self) -> Option<Vec<u8>> {
+        // type, flags, size (filled in at the end)
+        let mut ret = vec![self.data.ace_type(), self.flags.bits(), 0x00, 0x00];
+        match &self.data {
+            AceData::AccessAllowed { mask, sid }
+                    | AceData::AccessDenied { mask, sid }
+                    | AceData::SystemAudit { mask, sid }
+                    | AceData::AccessAllowedCallback { mask, sid }
+                    | AceData::AccessDeniedCallback { mask, sid }
+                    | AceData::SystemAuditCallback { mask, sid }
+                    | AceData::SystemScopedPolicyId { mask, sid } => {
+                ret.extend_from_slice(&mask.bits().to_le_bytes());
+                ret.extend_from_slice(&sid.try_to_bytes()?);
+            },
+            AceData::AccessAllowedObject { mask, flags, object_type, inherited_object_type, sid }
+                    | AceData::AccessDeniedObject { mask, flags, object_type, inherited_object_type, sid }
+                    | AceData::SystemAuditObject { mask, flags, object_type, inherited_object_type, sid }
+                    | AceData::AccessAllowedCallbackObject { mask, flags, object_type, inherited_object_type, sid }
+                    | AceData::AccessDeniedCallbackObject { mask, flags, object_type, inherited_object_type, sid }
+                    | AceData::SystemAuditCallbackObject { mask, flags, object_type, inherited_object_type, sid } => {
+                let object_flags =
+                    (flags & !0x3)
+                    | if object_type.is_some() { 0x1 } else { 0x0 }
+                    | if inherited_object_type.is_some() { 0x2 } else { 0x0 };
+                ret.extend_from_slice(&mask.bits().to_le_bytes());
+                ret.extend_from_slice(&object_flags.to_le_bytes());
+                if let Some(ot) = object_type {
+                    ret.extend_from_slice(&ot.to_bytes_le());
+                }
+                if let Some(iot) = inherited_object_type {
+                    ret.extend_from_slice(&iot.to_bytes_le());
+                }
+                ret.extend_from_slice(&sid.try_to_bytes()?);
+            },
+            AceData::SystemMandatoryLabel { mask, sid } => {
+                ret.extend_from_slice(&mask.bits().to_le_bytes());
+                ret.extend_from_slice(&sid.try_to_bytes()?);
+            },
+            AceData::SystemResourceAttribute { mask, sid, attribute_data } => {
+                ret.extend_from_slice(&mask.bits().to_le_bytes());
+                ret.extend_from_slice(&sid.try_to_bytes()?);
+                ret.extend_from_slice(&attribute_data.try_to_bytes()?);
+                // ACEs are aligned to four bytes; the claim is located via offsets, so padding is harmless
+                while ret.len() % 4 != 0 {
+                    ret.push(0x00);
+                }
+            },
+            AceData::Other { .. } => {},
+        }
+        ret.extend_from_slice(&self.application_data);
+
+        let ace_size = u16_length(ret.len())?;
+        ret[2..4].copy_from_slice(&ace_size.to_le_bytes());
+        Some(ret)
+    }
+
+    /// Parses an ACE in SDDL, including the surrounding parentheses, e.g. `(A;;RPLCRC;;;AU)`.
+    ///
+    /// The condition of a callback ACE (e.g. `(XA;;GR;;;AU;(@User.dept == "IT"))`) and the attribute
+    /// of a resource attribute ACE (e.g. `(RA;;;;;WD;("Project",TS,0x0,"Windows"))`) are parsed as
+    /// well.
+    pub fn from_sddl(s: &str) -> Result<Self, &'static str> {
+        let inner = s.strip_prefix('(')
+            .and_then(|i| i.strip_suffix(')'))
+            .ok_or("ACE is not enclosed in parentheses")?;
+        let fields = split_sddl_top_level(inner, ';');
+        if fields.len() < 6 || fields.len() > 7 {
+            return Err("ACE does not consist of six or seven fields");
+        }
+
+        // ACE types without a two-letter code are given in hexadecimal
+        let ace_type = match fields[0].strip_prefix("0x").or_else(|| fields[0].strip_prefix("0X")) {
+            Some(hex) => match u8::from_str_radix(hex, 16) {
+                Ok(0x0C) => "0xc",
+                Ok(0x0F) => "0xf",
+                _ => return Err("unknown or unsupported ACE type"),
+            },
+            None => fields[0],
+        };
+
+        let flags = flags_from_sddl(fields[1], &ACE_FLAGS_SDDL)?;
+        let object_type = guid_from_sddl(fields[3])?;
+        let inherited_object_type = guid_from_sddl(fields[4])?;
+        let sid = Sid::from_sddl(fields[5])?;
+
+        let object_flags =
+            if object_type.is_some() { 0x1 } else { 0x0 }
+            | if inherited_object_type.is_some() { 0x2 } else { 0x0 };
+        let is_object_ace = matches!(ace_type, "OA" | "OD" | "OU" | "ZA" | "0xc" | "0xf");
+        if object_flags != 0 && !is_object_ace {
+            return Err("object types are only allowed in object ACEs");
+        }
+
+        // the seventh field is the condition of a callback ACE or the attribute of a resource
+        // attribute ACE
+        let extra_field = fields.get(6).copied();
+        let is_callback_ace = matches!(ace_type, "XA" | "XD" | "ZA" | "XU" | "0xc" | "0xf");
+        let application_data = match extra_field {
+            Some(condition) if is_callback_ace => conditional_expression_from_sddl(condition)?,
+            Some(_) if ace_type == "RA" => Vec::new(),
+            Some(_) => return Err("only callback and resource attribute ACEs have a seventh field"),
+            None => Vec::new(),
+        };
+
+        let data = if ace_type == "ML" {
+            let mask = mask_from_sddl(fields[2], &MANDATORY_MASK_SDDL)?;
+            AceData::SystemMandatoryLabel { mask, sid }
+        } else {
+            let mask = mask_from_sddl(fields[2], &ACCESS_MASK_SDDL)?;
+            match ace_type {
+                "A" => AceData::AccessAllowed { mask, sid },
+                "D" => AceData::AccessDenied { mask, sid },
+                "AU" => AceData::SystemAudit { mask, sid },
+                "OA" => AceData::AccessAllowedObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "OD" => AceData::AccessDeniedObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "OU" => AceData::SystemAuditObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "XA" => AceData::AccessAllowedCallback { mask, sid },
+                "XD" => AceData::AccessDeniedCallback { mask, sid },
+                "ZA" => AceData::AccessAllowedCallbackObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "0xc" => AceData::AccessDeniedCallbackObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "XU" => AceData::SystemAuditCallback { mask, sid },
+                "0xf" => AceData::SystemAuditCallbackObject { mask, flags: object_flags, object_type, inherited_object_type, sid },
+                "RA" => {
+                    let attribute = extra_field
+                        .and_then(|f| f.strip_prefix('('))
+                        .and_then(|f| f.strip_suffix(')'))
+                        .ok_or("resource attribute ACE does not contain a parenthesized attribute")?;
+                    let attribute_data = ClaimSecurityAttribute1::from_sddl(attribute)?;
+                    AceData::SystemResourceAttribute { mask, sid, attribute_data }
+                },
+                "SP" => AceData::SystemScopedPolicyId { mask, sid },
+                _ => return Err("unknown or unsupported ACE type"),
+            }
+        };
+        let ace = Self {
+            flags,
+            data,
+            application_data,
+        };
+        if ace.try_to_bytes().is_none() {
+            return Err("ACE is longer than 65535 bytes");
+        }
+        Ok(ace)
+    }
 }
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/20233ed8-a6c6-4097-aafa-dd545ed24428
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[from_to_other(base_type = u8, derive_compare = "as_int")]
-pub(crate) enum AclRevision {
+pub enum AclRevision {
     Revision = 0x02,
     Ds = 0x04,
     Other(u8),
@@ -811,13 +1438,72 @@ impl Acl {
         })
     }
 
+    /// Serializes the ACL into its binary representation.
+    ///
+    /// Returns `None` if the ACL is longer than 65535 bytes or one of its ACEs cannot be serialized.
+    pub fn try_to_bytes(&self) -> Option<Vec<u8>> {
+        // revision, sbz1, size (filled in at the end), ACE count, sbz2
+        let mut ret = vec![u8::from(self.revision), self.sbz1, 0x00, 0x00];
+        ret.extend_from_slice(&u16_length(self.entries.len())?.to_le_bytes());
+        ret.extend_from_slice(&self.sbz2.to_le_bytes());
+        for ace in &self.entries {
+            ret.extend_from_slice(&ace.try_to_bytes()?);
+        }
+
+        let acl_size = u16_length(ret.len())?;
+        ret[2..4].copy_from_slice(&acl_size.to_le_bytes());
+        Some(ret)
+    }
+
+    /// Parses the ACEs of an ACL in SDDL, e.g. `(A;;RPLCRC;;;AU)(A;;GA;;;SY)`.
+    ///
+    /// As SDDL does not contain the revision of the ACL, it is chosen as Windows does: `Ds` if the
+    /// ACL contains object ACEs and `Revision` otherwise.
+    pub fn from_sddl(s: &str) -> Result<Self, &'static str> {
+        let entries = split_sddl_parenthesized(s)?
+            .into_iter()
+            .map(Ace::from_sddl)
+            .collect::<Result<Vec<_>, _>>()?;
+        let has_object_aces = entries.iter()
+            .any(|ace| matches!(
+                ace.data,
+                AceData::AccessAllowedObject { .. } | AceData::AccessDeniedObject { .. }
+                | AceData::SystemAuditObject { .. } | AceData::AccessAllowedCallbackObject { .. }
+                | AceData::AccessDeniedCallbackObject { .. } | AceData::SystemAuditCallbackObject { .. }
+            ));
+        let acl = Self {
+            revision: if has_object_aces { AclRevision::Ds } else { AclRevision::Revision },
+            sbz1: 0,
+            sbz2: 0,
+            entries,
+        };
+        if acl.try_to_bytes().is_none() {
+            return Err("ACL is longer than 65535 bytes");
+        }
+        Ok(acl)
+    }
+
     pub fn try_to_string(&self) -> Option<String> {
         let mut ret = String::new();
         for ace in &self.entries {
-            if ace.application_data.len() > 0 {
-                // bail out early
+            let is_callback = matches!(
+                ace.data,
+                AceData::AccessAllowedCallback { .. }
+                | AceData::AccessDeniedCallback { .. }
+                | AceData::AccessAllowedCallbackObject { .. }
+                | AceData::AccessDeniedCallbackObject { .. }
+                | AceData::SystemAuditCallback { .. }
+                | AceData::SystemAuditCallbackObject { .. }
+            );
+            let condition = if is_callback && !ace.application_data.is_empty() {
+                Some(conditional_expression_to_sddl(&ace.application_data)?)
+            } else if ace.application_data.iter().all(|b| *b == 0x00) {
+                // nothing or mere padding
+                None
+            } else {
+                // SDDL cannot represent any other application data
                 return None;
-            }
+            };
 
             write!(ret, "(").unwrap();
             let ace_type = match &ace.data {
@@ -830,99 +1516,22 @@ impl Acl {
                 AceData::AccessAllowedCallback { .. } => "XA",
                 AceData::AccessDeniedCallback { .. } => "XD",
                 AceData::AccessAllowedCallbackObject { .. } => "ZA",
-                AceData::AccessDeniedCallbackObject { .. } => return None, // unsupported!
+                // SDDL has no codes for these; output the ACE type in hexadecimal
+                AceData::AccessDeniedCallbackObject { .. } => "0xc",
                 AceData::SystemAuditCallback { .. } => "XU",
-                AceData::SystemAuditCallbackObject { .. } => return None, // unsupported!
+                AceData::SystemAuditCallbackObject { .. } => "0xf",
                 AceData::SystemMandatoryLabel { .. } => "ML",
                 AceData::SystemResourceAttribute { .. } => "RA",
                 AceData::SystemScopedPolicyId { .. } => "SP",
                 AceData::Other { .. } => return None,
             };
             write!(ret, "{};", ace_type).unwrap();
-            if ace.flags & AceFlags::all() != ace.flags {
-                // some of the flags are not known
-                return None;
-            }
-            if ace.flags.contains(AceFlags::ContainerInherit) {
-                write!(ret, "CI").unwrap();
-            }
-            if ace.flags.contains(AceFlags::ObjectInherit) {
-                write!(ret, "OI").unwrap();
-            }
-            if ace.flags.contains(AceFlags::NoPropagateInherit) {
-                write!(ret, "NP").unwrap();
-            }
-            if ace.flags.contains(AceFlags::InheritOnly) {
-                write!(ret, "IO").unwrap();
-            }
-            if ace.flags.contains(AceFlags::Inherited) {
-                write!(ret, "ID").unwrap();
-            }
-            if ace.flags.contains(AceFlags::SuccessfulAccessFlag) {
-                write!(ret, "SA").unwrap();
-            }
-            if ace.flags.contains(AceFlags::FailedAccessFlag) {
-                write!(ret, "FA").unwrap();
-            }
+            flags_to_sddl(ace.flags, &ACE_FLAGS_SDDL, &mut ret)?;
             write!(ret, ";").unwrap();
             if let Some(mask) = ace.data.access_mask() {
-                if mask & AccessMask::all() != mask {
-                    // contains unknown flags
-                    return None;
-                }
-                if mask.contains(AccessMask::DsCreateChild) {
-                    write!(ret, "CC").unwrap();
-                }
-                if mask.contains(AccessMask::DsDeleteChild) {
-                    write!(ret, "DC").unwrap();
-                }
-                if mask.contains(AccessMask::DsListChildren) {
-                    write!(ret, "LC").unwrap();
-                }
-                if mask.contains(AccessMask::DsSelfWrite) {
-                    write!(ret, "SW").unwrap();
-                }
-                if mask.contains(AccessMask::DsReadProp) {
-                    write!(ret, "RP").unwrap();
-                }
-                if mask.contains(AccessMask::DsWriteProp) {
-                    write!(ret, "WP").unwrap();
-                }
-                if mask.contains(AccessMask::DsDeleteTree) {
-                    write!(ret, "DT").unwrap();
-                }
-                if mask.contains(AccessMask::DsListObject) {
-                    write!(ret, "LO").unwrap();
-                }
-                if mask.contains(AccessMask::DsControlAccess) {
-                    write!(ret, "CR").unwrap();
-                }
-                if mask.contains(AccessMask::Delete) {
-                    write!(ret, "SD").unwrap();
-                }
-                if mask.contains(AccessMask::ReadControl) {
-                    write!(ret, "RC").unwrap();
-                }
-                if mask.contains(AccessMask::WriteDacl) {
-                    write!(ret, "WD").unwrap();
-                }
-                if mask.contains(AccessMask::WriteOwner) {
-                    write!(ret, "WO").unwrap();
-                }
+                mask_to_sddl(mask, &ACCESS_MASK_SDDL, &mut ret);
             } else if let AceData::SystemMandatoryLabel { mask, .. } = &ace.data {
-                if *mask & MandatoryMask::all() != *mask {
-                    // contains unknown flags
-                    return None;
-                }
-                if mask.contains(MandatoryMask::NoReadUp) {
-                    write!(ret, "NR").unwrap();
-                }
-                if mask.contains(MandatoryMask::NoWriteUp) {
-                    write!(ret, "NW").unwrap();
-                }
-                if mask.contains(MandatoryMask::NoExecuteUp) {
-                    write!(ret, "NX").unwrap();
-                }
+                mask_to_sddl(*mask, &MANDATORY_MASK_SDDL, &mut ret);
             } else {
                 return None;
             }
@@ -941,6 +1550,11 @@ impl Acl {
             } else {
                 return None;
             }
+            if let Some(condition) = condition {
+                write!(ret, ";{}", condition).unwrap();
+            } else if let AceData::SystemResourceAttribute { attribute_data, .. } = &ace.data {
+                write!(ret, ";({})", attribute_data.to_sddl()?).unwrap();
+            }
             write!(ret, ")").unwrap();
         }
         Some(ret)
@@ -1003,6 +1617,85 @@ bitflags! {
 }
 
 
+/// Splits a security descriptor in SDDL into its components (`O`, `G`, `D` and `S`) and their
+/// values.
+fn split_sddl_components(s: &str) -> Result<Vec<(char, &str)>, &'static str> {
+    // a component starts with its letter and a colon; colons only appear elsewhere within ACEs
+    let mut starts = Vec::new();
+    let mut depth: usize = 0;
+    let mut in_quotes = false;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth = depth.saturating_sub(1),
+            'O'|'G'|'D'|'S' if depth == 0 && !in_quotes && chars.peek().map(|(_, n)| *n) == Some(':') => {
+                starts.push((i, c));
+                chars.next();
+            },
+            _ => {},
+        }
+    }
+    if starts.first().map(|(i, _c)| *i) != Some(0) {
+        return Err("security descriptor does not start with a component");
+    }
+
+    let mut components: Vec<(char, &str)> = Vec::with_capacity(starts.len());
+    for (index, (start, component)) in starts.iter().enumerate() {
+        if components.iter().any(|(c, _value)| c == component) {
+            return Err("security descriptor contains the same component multiple times");
+        }
+        let end = starts.get(index + 1)
+            .map(|(i, _c)| *i)
+            .unwrap_or(s.len());
+        components.push((*component, &s[start+2..end]));
+    }
+    Ok(components)
+}
+
+
+/// Parses the flags and ACEs of an ACL component of a security descriptor in SDDL. Returns the
+/// corresponding security descriptor control flags and the ACL, which is `None` for a null ACL.
+fn acl_from_sddl(
+    s: &str,
+    protected: SecurityDescriptorControl,
+    auto_inherit_req: SecurityDescriptorControl,
+    auto_inherited: SecurityDescriptorControl,
+) -> Result<(SecurityDescriptorControl, Option<Acl>), &'static str> {
+    let aces_start = s.find('(').unwrap_or(s.len());
+    let (mut flags, aces) = s.split_at(aces_start);
+    let mut control = SecurityDescriptorControl::empty();
+    let mut is_null = false;
+    while !flags.is_empty() {
+        if let Some(rest) = flags.strip_prefix("NO_ACCESS_CONTROL") {
+            is_null = true;
+            flags = rest;
+        } else if let Some(rest) = flags.strip_prefix("AR") {
+            control |= auto_inherit_req;
+            flags = rest;
+        } else if let Some(rest) = flags.strip_prefix("AI") {
+            control |= auto_inherited;
+            flags = rest;
+        } else if let Some(rest) = flags.strip_prefix('P') {
+            control |= protected;
+            flags = rest;
+        } else {
+            return Err("unknown ACL flag");
+        }
+    }
+
+    if is_null {
+        if !aces.is_empty() {
+            return Err("null ACL contains ACEs");
+        }
+        Ok((control, None))
+    } else {
+        Ok((control, Some(Acl::from_sddl(aces)?)))
+    }
+}
+
+
 #[derive(Clone, Debug, Deserialize, Hash, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct SecurityDescriptor {
     pub revision: u8,
@@ -1014,6 +1707,17 @@ pub struct SecurityDescriptor {
     pub dacl: Option<Acl>,
 }
 impl SecurityDescriptor {
+    /// The SIDs of the owner, the group and the trustees of the ACEs, in order of appearance.
+    pub fn sids(&self) -> Vec<&Sid> {
+        let mut sids: Vec<&Sid> = self.owner.iter()
+            .chain(self.group.iter())
+            .collect();
+        for acl in self.sacl.iter().chain(self.dacl.iter()) {
+            sids.extend(acl.entries.iter().filter_map(|ace| ace.data.sid()));
+        }
+        sids
+    }
+
     pub fn try_from_bytes(bytes: &[u8]) -> Option<Self> {
         if bytes.len() < 20 {
             // revision, sbz1, control and the four offset fields are always present
@@ -1094,6 +1798,96 @@ impl SecurityDescriptor {
         })
     }
 
+    /// Serializes the security descriptor into its self-relative binary representation.
+    ///
+    /// As in the descriptors stored by Active Directory, the SACL and DACL are placed before the
+    /// owner and group SIDs.
+    ///
+    /// Returns `None` if one of its parts cannot be serialized.
+    pub fn try_to_bytes(&self) -> Option<Vec<u8>> {
+        // revision, sbz1, control and the four offsets (filled in as the parts are appended)
+        let mut ret = vec![0u8; 20];
+        ret[0] = self.revision;
+        ret[1] = self.sbz1;
+        ret[2..4].copy_from_slice(&self.control.bits().to_le_bytes());
+
+        let sacl_bytes = match &self.sacl {
+            Some(acl) => Some(acl.try_to_bytes()?),
+            None => None,
+        };
+        let dacl_bytes = match &self.dacl {
+            Some(acl) => Some(acl.try_to_bytes()?),
+            None => None,
+        };
+        let owner_bytes = match &self.owner {
+            Some(sid) => Some(sid.try_to_bytes()?),
+            None => None,
+        };
+        let group_bytes = match &self.group {
+            Some(sid) => Some(sid.try_to_bytes()?),
+            None => None,
+        };
+        let parts = [
+            (12, sacl_bytes),
+            (16, dacl_bytes),
+            (4, owner_bytes),
+            (8, group_bytes),
+        ];
+        for (offset_offset, part) in parts {
+            if let Some(part_bytes) = part {
+                let offset = u32_length(ret.len())?;
+                ret[offset_offset..offset_offset+4].copy_from_slice(&offset.to_le_bytes());
+                ret.extend_from_slice(&part_bytes);
+            }
+        }
+        Some(ret)
+    }
+
+    /// Parses a security descriptor in SDDL, e.g. `O:BAG:SYD:PAI(A;;RPLCRC;;;AU)`.
+    pub fn from_sddl(s: &str) -> Result<Self, &'static str> {
+        let mut control = SecurityDescriptorControl::SelfRelative;
+        let mut owner = None;
+        let mut group = None;
+        let mut dacl = None;
+        let mut sacl = None;
+        for (component, value) in split_sddl_components(s.trim())? {
+            match component {
+                'O' => owner = Some(Sid::from_sddl(value)?),
+                'G' => group = Some(Sid::from_sddl(value)?),
+                'D' => {
+                    let (acl_control, acl) = acl_from_sddl(
+                        value,
+                        SecurityDescriptorControl::DaclProtected,
+                        SecurityDescriptorControl::DaclAutoInheritReq,
+                        SecurityDescriptorControl::DaclAutoInherited,
+                    )?;
+                    control |= acl_control | SecurityDescriptorControl::DaclPresent;
+                    dacl = acl;
+                },
+                'S' => {
+                    let (acl_control, acl) = acl_from_sddl(
+                        value,
+                        SecurityDescriptorControl::SaclProtected,
+                        SecurityDescriptorControl::SaclAutoInheritReq,
+                        SecurityDescriptorControl::SaclAutoInherited,
+                    )?;
+                    control |= acl_control | SecurityDescriptorControl::SaclPresent;
+                    sacl = acl;
+                },
+                _ => unreachable!(),
+            }
+        }
+        Ok(Self {
+            revision: 1,
+            sbz1: 0,
+            control,
+            owner,
+            group,
+            sacl,
+            dacl,
+        })
+    }
+
     pub fn try_to_string(&self) -> Option<String> {
         if self.control.intersects(
                 SecurityDescriptorControl::OwnerDefaulted
@@ -1113,7 +1907,9 @@ impl SecurityDescriptor {
         if let Some(group) = self.group.as_ref() {
             write!(ret, "G:{}", group.to_sddl_sid_string()).unwrap();
         }
-        if let Some(dacl) = self.dacl.as_ref() {
+        // parts that have not been requested (e.g. using the SD flags control) are not present;
+        // an ACL that is marked as present but has no offset is a null ACL
+        if self.dacl.is_some() || self.control.contains(SecurityDescriptorControl::DaclPresent) {
             write!(ret, "D:").unwrap();
             if self.control.contains(SecurityDescriptorControl::DaclProtected) {
                 write!(ret, "P").unwrap();
@@ -1124,10 +1920,12 @@ impl SecurityDescriptor {
             if self.control.contains(SecurityDescriptorControl::DaclAutoInherited) {
                 write!(ret, "AI").unwrap();
             }
-            let acl_string = dacl.try_to_string()?;
-            write!(ret, "{}", acl_string).unwrap();
+            match self.dacl.as_ref() {
+                Some(dacl) => write!(ret, "{}", dacl.try_to_string()?).unwrap(),
+                None => write!(ret, "NO_ACCESS_CONTROL").unwrap(),
+            }
         }
-        if let Some(sacl) = self.sacl.as_ref() {
+        if self.sacl.is_some() || self.control.contains(SecurityDescriptorControl::SaclPresent) {
             write!(ret, "S:").unwrap();
             if self.control.contains(SecurityDescriptorControl::SaclProtected) {
                 write!(ret, "P").unwrap();
@@ -1138,8 +1936,10 @@ impl SecurityDescriptor {
             if self.control.contains(SecurityDescriptorControl::SaclAutoInherited) {
                 write!(ret, "AI").unwrap();
             }
-            let acl_string = sacl.try_to_string()?;
-            write!(ret, "{}", acl_string).unwrap();
+            match self.sacl.as_ref() {
+                Some(sacl) => write!(ret, "{}", sacl.try_to_string()?).unwrap(),
+                None => write!(ret, "NO_ACCESS_CONTROL").unwrap(),
+            }
         }
         Some(ret)
     }
@@ -1218,6 +2018,15 @@ impl CachedMembership {
             universal_sid_history,
         })
     }
+
+    /// The SIDs of the cached membership, in order of appearance.
+    pub fn sids(&self) -> Vec<&Sid> {
+        self.accounts.iter()
+            .chain(self.account_sid_history.iter())
+            .chain(self.universals.iter())
+            .chain(self.universal_sid_history.iter())
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Hash, Eq, Ord, PartialEq, PartialOrd, Serialize)]
@@ -1281,3 +2090,303 @@ pub fn logon_hours_to_string(logon_hours: &[u8]) -> Option<String> {
     }
     Some(ret)
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Ace, AceData, AceFlags, AccessMask, Acl, AclRevision, CachedMembership, ClaimFlags,
+        ClaimSecurityAttribute1, ClaimValues, SecurityDescriptor, SecurityDescriptorControl, Sid,
+        SidNames,
+    };
+
+    #[test]
+    fn test_missing_parts() {
+        // owner BA, no group, no SACL, DACL marked as present but null
+        const NULL_DACL: [u8; 36] = [
+            0x01, 0x00, 0x04, 0x80, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00,
+            0x20, 0x02, 0x00, 0x00,
+        ];
+        let sd = SecurityDescriptor::try_from_bytes(&NULL_DACL).unwrap();
+        assert_eq!(sd.try_to_string().unwrap(), "O:BAD:NO_ACCESS_CONTROL");
+        assert_eq!(sd.try_to_bytes().unwrap(), NULL_DACL);
+
+        // same, but with the DACL not requested
+        let mut owner_only = NULL_DACL;
+        owner_only[2] = 0x00;
+        let sd = SecurityDescriptor::try_from_bytes(&owner_only).unwrap();
+        assert_eq!(sd.try_to_string().unwrap(), "O:BA");
+    }
+
+    #[test]
+    fn test_sid_from_sddl() {
+        let administrators = Sid::from_sddl("BA").unwrap();
+        assert_eq!(administrators.to_string(), "S-1-5-32-544");
+        assert_eq!(Sid::from_sddl("S-1-5-32-544").unwrap(), administrators);
+        assert_eq!(Sid::from_sddl("S-1-5-21-1-2-3-500").unwrap().to_string(), "S-1-5-21-1-2-3-500");
+        assert!(Sid::from_sddl("XY").is_err());
+        assert!(Sid::from_sddl("").is_err());
+
+        let max_sid = format!("S-1-5{}", "-1".repeat(15));
+        assert!(Sid::from_sddl(&max_sid).unwrap().try_to_bytes().is_some());
+        assert!(Sid::from_sddl(&format!("{}-1", max_sid)).is_err());
+    }
+
+    #[test]
+    fn test_ace_from_sddl() {
+        let ace = Ace::from_sddl("(OA;CIIO;RPWP;bf967a7f-0de6-11d0-a285-00aa003049e2;bf967aba-0de6-11d0-a285-00aa003049e2;PS)").unwrap();
+        match ace.data {
+            AceData::AccessAllowedObject { mask, flags, object_type, inherited_object_type, sid } => {
+                assert_eq!(mask, AccessMask::DsReadProp | AccessMask::DsWriteProp);
+                assert_eq!(flags, 0x3);
+                assert_eq!(object_type.unwrap().to_string(), "bf967a7f-0de6-11d0-a285-00aa003049e2");
+                assert_eq!(inherited_object_type.unwrap().to_string(), "bf967aba-0de6-11d0-a285-00aa003049e2");
+                assert_eq!(sid.to_string(), "S-1-5-10");
+            },
+            other => panic!("unexpected ACE data {:?}", other),
+        }
+
+        let ace = Ace::from_sddl("(A;;0xf01ff;;;SY)").unwrap();
+        assert_eq!(ace.data.access_mask().unwrap().bits(), 0x000F_01FF);
+
+        assert!(Ace::from_sddl("A;;GA;;;SY").is_err());
+        assert!(Ace::from_sddl("(A;;GA;;SY)").is_err());
+        assert!(Ace::from_sddl("(Q;;GA;;;SY)").is_err());
+        assert!(Ace::from_sddl("(A;;QQ;;;SY)").is_err());
+        assert!(Ace::from_sddl("(A;;GA;bf967a7f-0de6-11d0-a285-00aa003049e2;;SY)").is_err());
+        assert!(Ace::from_sddl("(A;;GA;;;WD;(Member_of {SID(BA)}))").is_err());
+        assert!(Ace::from_sddl("(XA;;GA;;;WD;(Member_of {SID(BA)) && x)").is_err());
+        assert!(Ace::from_sddl("(RA;;;;;WD)").is_err());
+        assert!(Ace::from_sddl("(RA;;;;;WD;(\"Project\",TQ,0x0))").is_err());
+        assert!(Ace::from_sddl("(0x1;;GA;;;WD)").is_err());
+    }
+
+    #[test]
+    fn test_sddl_roundtrip() {
+        const SDDLS: [&str; 6] = [
+            "O:BAG:SYD:PAI(A;;CCDCLCSWRPWPDTLOCRSDRCWDWO;;;SY)(A;CIID;LCRPLORC;;;AU)(OA;CIIO;RP;4c164200-20c0-11d0-a768-00aa006e0529;4828cc14-1437-45bc-9b07-ad6f015e5f28;RU)",
+            "O:BAG:BAD:AI(D;;CC;;;WD)(A;;GA;;;BA)S:AI(AU;SAFA;WDWO;;;WD)",
+            "O:S-1-5-21-1-2-3-512G:S-1-5-21-1-2-3-513D:(A;;GR;;;S-1-5-21-1-2-3-1105)",
+            "O:BAD:NO_ACCESS_CONTROL",
+            "D:(A;;0xf01ff;;;SY)",
+            "S:(ML;;NRNW;;;HI)",
+        ];
+        for sddl in SDDLS {
+            let sd = SecurityDescriptor::from_sddl(sddl).unwrap();
+            assert_eq!(sd.try_to_string().unwrap(), sddl.replace("0xf01ff", "CCDCLCSWRPWPDTLOCRSDRCWDWO"));
+        }
+
+        let sd = SecurityDescriptor::from_sddl("O:BAD:PAI(A;;GA;;;SY)").unwrap();
+        assert_eq!(
+            sd.control,
+            SecurityDescriptorControl::SelfRelative
+                | SecurityDescriptorControl::DaclPresent
+                | SecurityDescriptorControl::DaclProtected
+                | SecurityDescriptorControl::DaclAutoInherited,
+        );
+        assert_eq!(sd.group, None);
+        assert_eq!(sd.sacl, None);
+
+        assert!(SecurityDescriptor::from_sddl("X:BA").is_err());
+        assert!(SecurityDescriptor::from_sddl("O:BAO:SY").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:Q(A;;GA;;;SY)").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:NO_ACCESS_CONTROL(A;;GA;;;SY)").is_err());
+
+        // oversized input is rejected instead of failing to serialize
+        let long_owner = format!("O:S-1-5{}", "-1".repeat(300));
+        assert!(SecurityDescriptor::from_sddl(&long_owner).is_err());
+        let dacl = |ace_count| format!("D:{}", "(A;;GA;;;S-1-5-21-1-2-3-1104)".repeat(ace_count));
+        assert!(SecurityDescriptor::from_sddl(&dacl(200)).unwrap().try_to_bytes().is_some());
+        assert!(SecurityDescriptor::from_sddl(&dacl(2000)).is_err());
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let sid = Sid::from_sddl("S-1-5-21-1004336348-1177238915-682003330-512").unwrap();
+        let sid_bytes = sid.try_to_bytes().unwrap();
+        assert_eq!(&sid_bytes[0..8], &[0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05]);
+        assert_eq!(&sid_bytes[24..28], &[0x00, 0x02, 0x00, 0x00]);
+        assert_eq!(Sid::try_from_bytes(&sid_bytes).unwrap(), sid);
+
+        let sd = SecurityDescriptor::from_sddl(
+            "O:BAG:SYD:PAI(A;;CCDCLCSWRPWPDTLOCRSDRCWDWO;;;SY)(OA;CIIO;RP;4c164200-20c0-11d0-a768-00aa006e0529;4828cc14-1437-45bc-9b07-ad6f015e5f28;RU)(OD;;CR;00299570-246d-11d0-a768-00aa006e0529;;WD)S:AI(AU;SAFA;WDWO;;;WD)(ML;;NW;;;HI)",
+        ).unwrap();
+        let sd_bytes = sd.try_to_bytes().unwrap();
+        assert_eq!(SecurityDescriptor::try_from_bytes(&sd_bytes).unwrap(), sd);
+        for acl in [sd.dacl.as_ref().unwrap(), sd.sacl.as_ref().unwrap()] {
+            for ace in &acl.entries {
+                let ace_bytes = ace.try_to_bytes().unwrap();
+                assert_eq!(ace_bytes.len() % 4, 0);
+                assert_eq!(Ace::try_from_bytes(&ace_bytes).as_ref(), Some(ace));
+            }
+        }
+
+        // callback ACE with a conditional expression, kept as application data
+        let callback = Ace {
+            flags: AceFlags::empty(),
+            data: AceData::AccessAllowedCallback { mask: AccessMask::GenericRead, sid: sid.clone() },
+            application_data: vec![0x61, 0x72, 0x74, 0x78, 0x00, 0x00, 0x00, 0x00],
+        };
+        assert_eq!(Ace::try_from_bytes(&callback.try_to_bytes().unwrap()).unwrap(), callback);
+
+        let resource_attribute = Ace {
+            flags: AceFlags::empty(),
+            data: AceData::SystemResourceAttribute {
+                mask: AccessMask::empty(),
+                sid: Sid::from_sddl("WD").unwrap(),
+                attribute_data: ClaimSecurityAttribute1 {
+                    name: "Secrecy".to_owned(),
+                    reserved: 0,
+                    flags: ClaimFlags::ValueCaseSensitive,
+                    values: ClaimValues::Strings(vec!["High".to_owned(), "Medium".to_owned()]),
+                },
+            },
+            application_data: Vec::new(),
+        };
+        let resource_attribute_bytes = resource_attribute.try_to_bytes().unwrap();
+        assert_eq!(resource_attribute_bytes.len() % 4, 0);
+        assert_eq!(Ace::try_from_bytes(&resource_attribute_bytes).unwrap(), resource_attribute);
+
+        // too long for the size fields
+        let long_sid = Sid { version: 1, authority: 5, subauthorities: vec![1; 300] };
+        assert_eq!(long_sid.try_to_bytes(), None);
+        let long_acl = Acl {
+            revision: AclRevision::Revision,
+            sbz1: 0,
+            sbz2: 0,
+            entries: vec![callback; 4096],
+        };
+        assert_eq!(long_acl.try_to_bytes(), None);
+    }
+
+    #[test]
+    fn test_sddl_extensions() {
+        // @User.dept == "IT"
+        let condition = vec![
+            0x61, 0x72, 0x74, 0x78, 0xF9, 0x08, 0x00, 0x00, 0x00, 0x64, 0x00, 0x65, 0x00, 0x70, 0x00, 0x74,
+            0x00, 0x10, 0x04, 0x00, 0x00, 0x00, 0x49, 0x00, 0x54, 0x00, 0x80, 0x00,
+        ];
+        let mut acl = Acl {
+            revision: AclRevision::Revision,
+            sbz1: 0,
+            sbz2: 0,
+            entries: vec![
+                Ace {
+                    flags: AceFlags::empty(),
+                    data: AceData::AccessAllowedCallback { mask: AccessMask::GenericRead, sid: Sid::from_sddl("AU").unwrap() },
+                    application_data: condition,
+                },
+                Ace {
+                    flags: AceFlags::empty(),
+                    data: AceData::AccessAllowed { mask: AccessMask::from_bits_retain(0x001F_01FF), sid: Sid::from_sddl("SY").unwrap() },
+                    application_data: Vec::new(),
+                },
+                Ace {
+                    flags: AceFlags::empty(),
+                    data: AceData::SystemResourceAttribute {
+                        mask: AccessMask::empty(),
+                        sid: Sid::from_sddl("WD").unwrap(),
+                        attribute_data: ClaimSecurityAttribute1 {
+                            name: "Project".to_owned(),
+                            reserved: 0,
+                            flags: ClaimFlags::empty(),
+                            values: ClaimValues::Strings(vec!["Windows".to_owned(), "SQL".to_owned()]),
+                        },
+                    },
+                    application_data: Vec::new(),
+                },
+            ],
+        };
+        assert_eq!(
+            acl.try_to_string().unwrap(),
+            concat!(
+                "(XA;;GR;;;AU;(@User.dept == \"IT\"))",
+                "(A;;0x1f01ff;;;SY)",
+                "(RA;;;;;WD;(\"Project\",TS,0x0,\"Windows\",\"SQL\"))",
+            ),
+        );
+
+        // conditions and resource attributes can be parsed back
+        assert_eq!(Acl::from_sddl(&acl.try_to_string().unwrap()).unwrap(), acl);
+
+        // application data of a non-callback ACE has no SDDL representation
+        acl.entries[1].application_data = vec![0x01, 0x02, 0x03, 0x04];
+        assert_eq!(acl.try_to_string(), None);
+
+        let mut octet_strings = ClaimSecurityAttribute1 {
+            name: "Hash".to_owned(),
+            reserved: 0,
+            flags: ClaimFlags::ValueCaseSensitive,
+            values: ClaimValues::OctetStrings(vec![vec![0x0A, 0x0B], Vec::new()]),
+        };
+        assert_eq!(octet_strings.to_sddl().unwrap(), "\"Hash\",TX,0x2,#0a0b,#");
+
+        // SDDL strings cannot contain double quotes
+        octet_strings.values = ClaimValues::Strings(vec!["say \"cheese\"".to_owned()]);
+        assert_eq!(octet_strings.to_sddl(), None);
+
+        // unknown mandatory label bits are output in hexadecimal and can be parsed back
+        let sd = SecurityDescriptor::from_sddl("S:(ML;;0x9;;;HI)").unwrap();
+        assert_eq!(sd.try_to_string().unwrap(), "S:(ML;;0x9;;;HI)");
+    }
+
+    #[test]
+    fn test_callback_object_aces() {
+        // SDDL has no codes for denied and audit callback object ACEs
+        let sddl = concat!(
+            "D:(0xc;;CR;00299570-246d-11d0-a768-00aa006e0529;;WD;(@User.clearance >= 3))(A;;GA;;;SY)",
+            "S:(0xf;SA;WP;;bf967aba-0de6-11d0-a285-00aa003049e2;WD)",
+        );
+        let sd = SecurityDescriptor::from_sddl(sddl).unwrap();
+        let dacl = sd.dacl.as_ref().unwrap();
+        assert_eq!(dacl.revision, AclRevision::Ds);
+        match &dacl.entries[0].data {
+            AceData::AccessDeniedCallbackObject { mask, flags, object_type, inherited_object_type, sid } => {
+                assert_eq!(*mask, AccessMask::DsControlAccess);
+                assert_eq!(*flags, 0x1);
+                assert_eq!(object_type.unwrap().to_string(), "00299570-246d-11d0-a768-00aa006e0529");
+                assert_eq!(*inherited_object_type, None);
+                assert_eq!(sid.to_string(), "S-1-1-0");
+            },
+            other => panic!("unexpected ACE data {:?}", other),
+        }
+        assert!(dacl.entries[0].application_data.starts_with(b"artx"));
+        match &sd.sacl.as_ref().unwrap().entries[0].data {
+            AceData::SystemAuditCallbackObject { mask, flags, .. } => {
+                assert_eq!(*mask, AccessMask::DsWriteProp);
+                assert_eq!(*flags, 0x2);
+            },
+            other => panic!("unexpected ACE data {:?}", other),
+        }
+
+        // the rest of the descriptor is output as usual
+        let sd = SecurityDescriptor::try_from_bytes(&sd.try_to_bytes().unwrap()).unwrap();
+        assert_eq!(sd.try_to_string().unwrap(), sddl);
+    }
+
+    #[test]
+    fn test_sid_names() {
+        let user = Sid::from_sddl("S-1-5-21-7-8-9-1104").unwrap();
+        let unknown = Sid::from_sddl("S-1-5-21-7-8-9-1105").unwrap();
+        let mut sid_names = SidNames::new();
+        sid_names.insert(user.clone(), "EXAMPLE\\jdoe".to_owned());
+        assert_eq!(user.to_string_with_name(&sid_names), "S-1-5-21-7-8-9-1104 (EXAMPLE\\jdoe)");
+        assert_eq!(unknown.to_string_with_name(&sid_names), "S-1-5-21-7-8-9-1105");
+
+        let sd = SecurityDescriptor::from_sddl(
+            "O:S-1-5-21-7-8-9-1104G:BAD:(A;;GA;;;S-1-5-21-7-8-9-1105)(A;;GR;;;S-1-5-21-7-8-9-1104)",
+        ).unwrap();
+        let administrators = Sid::from_sddl("BA").unwrap();
+        assert_eq!(sd.sids(), [&user, &administrators, &unknown, &user]);
+
+        let membership = CachedMembership {
+            version: 1,
+            accounts: vec![user.clone()],
+            account_sid_history: Vec::new(),
+            universals: vec![unknown.clone(), administrators.clone()],
+            universal_sid_history: Vec::new(),
+        };
+        assert_eq!(membership.sids(), [&user, &unknown, &administrators]);
+    }
+}
@@ -0,0 +1,546 @@
+//! Conditional expressions of callback ACEs.
+//!
+//! The binary format is described in MS-DTYP section 2.4.4.17, the SDDL format in section 2.5.1.1.
+
+
+use crate::values::structs::security::{
+    parse_sddl_octet_string_literal, parse_sddl_string_literal, sddl_octet_string_literal,
+    sddl_string_literal, Sid,
+};
+
+
+/// The signature with which the application data of a callback ACE starts if it contains a
+/// conditional expression.
+const CONDITIONAL_SIGNATURE: [u8; 4] = *b"artx";
+
+
+/// Relational operators that take two operands, as (token, SDDL operator).
+const BINARY_RELATIONAL_OPERATORS: [(u8, &str); 10] = [
+    (0x80, "=="),
+    (0x81, "!="),
+    (0x82, "<"),
+    (0x83, "<="),
+    (0x84, ">"),
+    (0x85, ">="),
+    (0x86, "Contains"),
+    (0x88, "Any_of"),
+    (0x8E, "Not_Contains"),
+    (0x8F, "Not_Any_of"),
+];
+
+/// Relational operators that take one operand, as (token, SDDL operator).
+const UNARY_RELATIONAL_OPERATORS: [(u8, &str); 10] = [
+    (0x87, "Exists"),
+    (0x89, "Member_of"),
+    (0x8A, "Device_Member_of"),
+    (0x8B, "Member_of_Any"),
+    (0x8C, "Device_Member_of_Any"),
+    (0x8D, "Not_Exists"),
+    (0x90, "Not_Member_of"),
+    (0x91, "Not_Device_Member_of"),
+    (0x92, "Not_Member_of_Any"),
+    (0x93, "Not_Device_Member_of_Any"),
+];
+
+/// The prefixes of the attribute names, as (token, SDDL prefix).
+const ATTRIBUTE_PREFIXES: [(u8, &str); 4] = [
+    (0xF8, ""),
+    (0xF9, "@User."),
+    (0xFA, "@Resource."),
+    (0xFB, "@Device."),
+];
+
+
+/// Reads the length-prefixed data following a token, returning it and the total number of bytes
+/// taken up by the length and the data.
+fn length_prefixed(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let length: usize = u32::from_le_bytes(bytes[0..4].try_into().unwrap()).try_into().unwrap();
+    let data = bytes.get(4..4+length)?;
+    Some((data, 4 + length))
+}
+
+
+/// Appends a token followed by length-prefixed data.
+fn push_length_prefixed(bytes: &mut Vec<u8>, token: u8, data: &[u8]) {
+    bytes.push(token);
+    bytes.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+    bytes.extend_from_slice(data);
+}
+
+
+fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .flat_map(|w| w.to_le_bytes())
+        .collect()
+}
+
+
+fn utf16le_string(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let words: Vec<u16> = bytes.chunks(2)
+        .map(|w| u16::from_le_bytes(w.try_into().unwrap()))
+        .collect();
+    String::from_utf16(&words).ok()
+}
+
+
+/// Converts the literal or attribute name starting with the given token into SDDL, returning it
+/// and the number of bytes following the token that it takes up.
+fn operand_to_sddl(token: u8, bytes: &[u8]) -> Option<(String, usize)> {
+    match token {
+        0x01..=0x04 => {
+            // signed integers of various sizes, all stored as 64-bit value, sign and base
+            if bytes.len() < 10 {
+                return None;
+            }
+            let value = i64::from_le_bytes(bytes[0..8].try_into().unwrap());
+            let sign = match (bytes[8], value < 0) {
+                (0x02, _)|(_, true) => "-",
+                (0x01, false) => "+",
+                (0x03, false) => "",
+                _ => return None,
+            };
+            let magnitude = value.unsigned_abs();
+            let literal = match bytes[9] {
+                0x01 => format!("{}0{:o}", sign, magnitude),
+                0x02 => format!("{}{}", sign, magnitude),
+                0x03 => format!("{}0x{:x}", sign, magnitude),
+                _ => return None,
+            };
+            Some((literal, 10))
+        },
+        0x10 => {
+            let (data, length) = length_prefixed(bytes)?;
+            Some((sddl_string_literal(&utf16le_string(data)?)?, length))
+        },
+        0x18 => {
+            let (data, length) = length_prefixed(bytes)?;
+            Some((sddl_octet_string_literal(data), length))
+        },
+        0x50 => {
+            let (data, length) = length_prefixed(bytes)?;
+            let mut elements = Vec::new();
+            let mut pos = 0;
+            while pos < data.len() {
+                let element_token = data[pos];
+                if ATTRIBUTE_PREFIXES.iter().any(|(t, _prefix)| *t == element_token) {
+                    // composites only contain literals
+                    return None;
+                }
+                let (element, element_length) = operand_to_sddl(element_token, &data[pos+1..])?;
+                elements.push(element);
+                pos += 1 + element_length;
+            }
+            Some((format!("{{{}}}", elements.join(", ")), length))
+        },
+        0x51 => {
+            let (data, length) = length_prefixed(bytes)?;
+            let sid = Sid::try_from_bytes(data)?;
+            Some((format!("SID({})", sid.to_sddl_sid_string()), length))
+        },
+        _ => {
+            let (_token, prefix) = ATTRIBUTE_PREFIXES.iter()
+                .find(|(t, _prefix)| *t == token)?;
+            let (data, length) = length_prefixed(bytes)?;
+            Some((format!("{}{}", prefix, utf16le_string(data)?), length))
+        },
+    }
+}
+
+
+/// Converts the application data of a callback ACE into the SDDL of its conditional expression,
+/// e.g. `(@User.dept == "IT")`.
+///
+/// Returns `None` if the application data is not a valid conditional expression.
+pub fn conditional_expression_to_sddl(application_data: &[u8]) -> Option<String> {
+    let tokens = application_data.strip_prefix(&CONDITIONAL_SIGNATURE)?;
+
+    // the expression is stored in postfix notation
+    let mut stack: Vec<String> = Vec::new();
+    let mut pos = 0;
+    while pos < tokens.len() {
+        let token = tokens[pos];
+        pos += 1;
+
+        if token == 0x00 {
+            // padding to a multiple of four bytes; only valid at the end
+            if tokens[pos..].iter().any(|b| *b != 0x00) {
+                return None;
+            }
+            break;
+        }
+
+        if let Some((_token, operator)) = BINARY_RELATIONAL_OPERATORS.iter().find(|(t, _op)| *t == token) {
+            let right = stack.pop()?;
+            let left = stack.pop()?;
+            stack.push(format!("({} {} {})", left, operator, right));
+        } else if let Some((_token, operator)) = UNARY_RELATIONAL_OPERATORS.iter().find(|(t, _op)| *t == token) {
+            let operand = stack.pop()?;
+            stack.push(format!("({} {})", operator, operand));
+        } else if token == 0xA0 || token == 0xA1 {
+            let operator = if token == 0xA0 { "&&" } else { "||" };
+            let right = stack.pop()?;
+            let left = stack.pop()?;
+            stack.push(format!("({} {} {})", left, operator, right));
+        } else if token == 0xA2 {
+            let operand = stack.pop()?;
+            stack.push(format!("(!{})", operand));
+        } else {
+            let (operand, length) = operand_to_sddl(token, &tokens[pos..])?;
+            stack.push(operand);
+            pos += length;
+        }
+    }
+
+    let expression = stack.pop()?;
+    if !stack.is_empty() {
+        return None;
+    }
+    if expression.starts_with('(') {
+        Some(expression)
+    } else {
+        // a lone attribute or literal
+        Some(format!("({})", expression))
+    }
+}
+
+
+/// Parses the SDDL of a conditional expression, e.g. `(@User.dept == "IT")`, into the application
+/// data of a callback ACE, padded to a multiple of four bytes.
+///
+/// Integer literals are always stored as 64-bit values.
+pub fn conditional_expression_from_sddl(s: &str) -> Result<Vec<u8>, &'static str> {
+    let mut parser = ConditionParser { rest: s };
+    let mut ret = CONDITIONAL_SIGNATURE.to_vec();
+    parser.or_expression(&mut ret)?;
+    parser.skip_whitespace();
+    if !parser.rest.is_empty() {
+        return Err("unexpected characters after conditional expression");
+    }
+    while !ret.len().is_multiple_of(4) {
+        ret.push(0x00);
+    }
+    Ok(ret)
+}
+
+
+/// A recursive-descent parser for conditional expressions in SDDL, outputting the binary tokens in
+/// postfix notation.
+///
+/// The precedence of the operators, from highest to lowest, is: relational operators, `!`, `&&`,
+/// `||`.
+struct ConditionParser<'a> {
+    rest: &'a str,
+}
+impl<'a> ConditionParser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    /// Consumes the given string if the input (after any whitespace) starts with it.
+    fn eat(&mut self, prefix: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(prefix) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Takes the longest prefix of the input consisting of characters matching the predicate.
+    fn take_while<P: Fn(char) -> bool>(&mut self, predicate: P) -> &'a str {
+        let end = self.rest.find(|c| !predicate(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    /// The word (letters, digits and underscores) at the start of the input.
+    fn peek_word(&self) -> &'a str {
+        let end = self.rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(self.rest.len());
+        &self.rest[..end]
+    }
+
+    fn or_expression(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.and_expression(out)?;
+        while self.eat("||") {
+            self.and_expression(out)?;
+            out.push(0xA1);
+        }
+        Ok(())
+    }
+
+    fn and_expression(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.not_expression(out)?;
+        while self.eat("&&") {
+            self.not_expression(out)?;
+            out.push(0xA0);
+        }
+        Ok(())
+    }
+
+    fn not_expression(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        if self.eat("!") {
+            self.not_expression(out)?;
+            out.push(0xA2);
+        } else if self.eat("(") {
+            self.or_expression(out)?;
+            if !self.eat(")") {
+                return Err("missing closing parenthesis in conditional expression");
+            }
+        } else {
+            self.relation(out)?;
+        }
+        Ok(())
+    }
+
+    fn relation(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        let word = self.peek_word();
+        if let Some((token, _operator)) = UNARY_RELATIONAL_OPERATORS.iter().find(|(_t, op)| op.eq_ignore_ascii_case(word)) {
+            self.rest = &self.rest[word.len()..];
+            self.operand(out)?;
+            out.push(*token);
+            return Ok(());
+        }
+
+        self.operand(out)?;
+        self.skip_whitespace();
+        let word = self.peek_word();
+        let rest = self.rest;
+        let binary_operator = BINARY_RELATIONAL_OPERATORS.iter()
+            .filter(|(_t, op)| {
+                if op.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    op.eq_ignore_ascii_case(word)
+                } else {
+                    rest.starts_with(op)
+                }
+            })
+            // e.g. `<=` instead of `<`
+            .max_by_key(|(_t, op)| op.len());
+        if let Some((token, operator)) = binary_operator {
+            self.rest = &self.rest[operator.len()..];
+            self.operand(out)?;
+            out.push(*token);
+        }
+        // otherwise, a lone attribute or literal
+        Ok(())
+    }
+
+    fn operand(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        for (token, prefix) in &ATTRIBUTE_PREFIXES {
+            let has_prefix = self.rest.get(..prefix.len())
+                .map(|p| p.eq_ignore_ascii_case(prefix))
+                .unwrap_or(false);
+            if prefix.is_empty() || !has_prefix {
+                continue;
+            }
+            self.rest = &self.rest[prefix.len()..];
+            return self.attribute_name(*token, out);
+        }
+
+        let is_literal = self.rest.starts_with(['"', '#', '{', '+', '-'])
+            || self.rest.starts_with(|c: char| c.is_ascii_digit())
+            || self.rest.get(..4).map(|p| p.eq_ignore_ascii_case("SID(")).unwrap_or(false);
+        if is_literal {
+            self.literal(out)
+        } else {
+            // local attribute
+            self.attribute_name(0xF8, out)
+        }
+    }
+
+    fn attribute_name(&mut self, token: u8, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        let name = self.take_while(|c| c.is_alphanumeric() || "_:./%".contains(c));
+        if name.is_empty() {
+            return Err("expected an attribute name in conditional expression");
+        }
+        push_length_prefixed(out, token, &utf16le(name));
+        Ok(())
+    }
+
+    fn literal(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        if self.rest.starts_with('"') {
+            let end = self.rest[1..].find('"')
+                .ok_or("unterminated string literal in conditional expression")?;
+            let (literal, rest) = self.rest.split_at(end + 2);
+            self.rest = rest;
+            let value = parse_sddl_string_literal(literal)?;
+            push_length_prefixed(out, 0x10, &utf16le(&value));
+        } else if self.rest.starts_with('#') {
+            self.rest = &self.rest[1..];
+            let hex = self.take_while(|c| c.is_ascii_hexdigit());
+            let value = parse_sddl_octet_string_literal(&format!("#{}", hex))?;
+            push_length_prefixed(out, 0x18, &value);
+        } else if self.eat("{") {
+            let mut elements = Vec::new();
+            if !self.eat("}") {
+                loop {
+                    self.literal(&mut elements)?;
+                    if self.eat("}") {
+                        break;
+                    } else if !self.eat(",") {
+                        return Err("expected a comma or closing brace in composite literal");
+                    }
+                }
+            }
+            push_length_prefixed(out, 0x50, &elements);
+        } else if self.rest.get(..4).map(|p| p.eq_ignore_ascii_case("SID(")).unwrap_or(false) {
+            self.rest = &self.rest[4..];
+            let sid_string = self.take_while(|c| c != ')');
+            if !self.eat(")") {
+                return Err("unterminated SID literal in conditional expression");
+            }
+            let sid = Sid::from_sddl(sid_string.trim())?;
+            let sid_bytes = sid.try_to_bytes()
+                .ok_or("SID in conditional expression has too many subauthorities")?;
+            push_length_prefixed(out, 0x51, &sid_bytes);
+        } else {
+            self.integer(out)?;
+        }
+        Ok(())
+    }
+
+    fn integer(&mut self, out: &mut Vec<u8>) -> Result<(), &'static str> {
+        // the sign must be directly followed by the digits
+        let (sign, negative) = if let Some(rest) = self.rest.strip_prefix('-') {
+            self.rest = rest;
+            (0x02, true)
+        } else if let Some(rest) = self.rest.strip_prefix('+') {
+            self.rest = rest;
+            (0x01, false)
+        } else {
+            (0x03, false)
+        };
+        let (base, magnitude) = if let Some(rest) = self.rest.strip_prefix("0x").or_else(|| self.rest.strip_prefix("0X")) {
+            self.rest = rest;
+            let hex = self.take_while(|c| c.is_ascii_hexdigit());
+            (0x03, u64::from_str_radix(hex, 16))
+        } else {
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            if digits.len() > 1 && digits.starts_with('0') {
+                (0x01, u64::from_str_radix(&digits[1..], 8))
+            } else {
+                (0x02, digits.parse())
+            }
+        };
+        let magnitude = magnitude
+            .map_err(|_| "invalid integer literal in conditional expression")?;
+        let value = if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+            .ok_or("integer literal in conditional expression is out of range")?;
+        out.push(0x04);
+        out.extend_from_slice(&value.to_le_bytes());
+        out.push(sign);
+        out.push(base);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        conditional_expression_from_sddl, conditional_expression_to_sddl, push_length_prefixed,
+        utf16le,
+    };
+
+    #[test]
+    fn test_conditional_expression_to_sddl() {
+        // @User.dept == "IT"
+        let mut bytes = b"artx".to_vec();
+        push_length_prefixed(&mut bytes, 0xF9, &utf16le("dept"));
+        push_length_prefixed(&mut bytes, 0x10, &utf16le("IT"));
+        bytes.push(0x80);
+        assert_eq!(conditional_expression_to_sddl(&bytes).unwrap(), "(@User.dept == \"IT\")");
+
+        // ... && !(Member_of {SID(BA), SID(S-1-5-21-1-2-3-1104)}), padded
+        let mut composite = Vec::new();
+        push_length_prefixed(&mut composite, 0x51, &[0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00]);
+        push_length_prefixed(&mut composite, 0x51, &[
+            0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00,
+        ]);
+        push_length_prefixed(&mut bytes, 0x50, &composite);
+        bytes.extend_from_slice(&[0x89, 0xA2, 0xA0]);
+        while !bytes.len().is_multiple_of(4) {
+            bytes.push(0x00);
+        }
+        assert_eq!(
+            conditional_expression_to_sddl(&bytes).unwrap(),
+            "((@User.dept == \"IT\") && (!(Member_of {SID(BA), SID(S-1-5-21-1-2-3-1104)})))",
+        );
+
+        // Exists clearance || @Device.level >= -0x10
+        let mut bytes = b"artx".to_vec();
+        push_length_prefixed(&mut bytes, 0xF8, &utf16le("clearance"));
+        bytes.push(0x87);
+        push_length_prefixed(&mut bytes, 0xFB, &utf16le("level"));
+        bytes.push(0x04);
+        bytes.extend_from_slice(&(-16i64).to_le_bytes());
+        bytes.extend_from_slice(&[0x02, 0x03, 0x85, 0xA1]);
+        assert_eq!(
+            conditional_expression_to_sddl(&bytes).unwrap(),
+            "((Exists clearance) || (@Device.level >= -0x10))",
+        );
+
+        // no signature, missing operand, unknown token, leftover operand
+        assert_eq!(conditional_expression_to_sddl(&[0x00, 0x00, 0x00, 0x00]), None);
+        assert_eq!(conditional_expression_to_sddl(&[0x61, 0x72, 0x74, 0x78, 0x80]), None);
+        assert_eq!(conditional_expression_to_sddl(&[0x61, 0x72, 0x74, 0x78, 0x42]), None);
+        let mut bytes = b"artx".to_vec();
+        push_length_prefixed(&mut bytes, 0xF8, &utf16le("a"));
+        push_length_prefixed(&mut bytes, 0xF8, &utf16le("b"));
+        assert_eq!(conditional_expression_to_sddl(&bytes), None);
+    }
+
+    #[test]
+    fn test_conditional_expression_from_sddl() {
+        const SDDLS: [&str; 5] = [
+            "(@User.dept == \"IT\")",
+            "((@User.dept == \"IT\") && (!(Member_of {SID(BA), SID(S-1-5-21-1-2-3-1104)})))",
+            "((Exists clearance) || (@Device.level >= -0x10))",
+            "((@Resource.hash != #0a0b) && (@User.level < 010))",
+            "(@User.teams Any_of {\"a\", \"b\", +5})",
+        ];
+        for sddl in SDDLS {
+            let bytes = conditional_expression_from_sddl(sddl).unwrap();
+            assert_eq!(bytes.len() % 4, 0);
+            assert_eq!(conditional_expression_to_sddl(&bytes).unwrap(), sddl);
+        }
+
+        // same bytes as produced by Windows for @User.dept == "IT"
+        let mut bytes = b"artx".to_vec();
+        push_length_prefixed(&mut bytes, 0xF9, &utf16le("dept"));
+        push_length_prefixed(&mut bytes, 0x10, &utf16le("IT"));
+        bytes.extend_from_slice(&[0x80, 0x00]);
+        assert_eq!(conditional_expression_from_sddl("(@User.dept==\"IT\")").unwrap(), bytes);
+
+        // precedence and case-insensitive operators
+        let bytes = conditional_expression_from_sddl("(a || b && !c)").unwrap();
+        assert_eq!(conditional_expression_to_sddl(&bytes).unwrap(), "(a || (b && (!c)))");
+        let bytes = conditional_expression_from_sddl("(member_of{sid(BA)})").unwrap();
+        assert_eq!(conditional_expression_to_sddl(&bytes).unwrap(), "(Member_of {SID(BA)})");
+
+        assert!(conditional_expression_from_sddl("(a ==)").is_err());
+        assert!(conditional_expression_from_sddl("(a == \"b)").is_err());
+        assert!(conditional_expression_from_sddl("(a) b").is_err());
+        assert!(conditional_expression_from_sddl("(Member_of {SID(BA)}").is_err());
+        assert!(conditional_expression_from_sddl("(a == 99999999999999999999)").is_err());
+        assert!(conditional_expression_from_sddl("(a == #abc)").is_err());
+        assert!(conditional_expression_from_sddl("(a == - 5)").is_err());
+        assert!(conditional_expression_from_sddl("(a == 5abc)").is_err());
+    }
+}
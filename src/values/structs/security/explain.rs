@@ -0,0 +1,358 @@
+//! Explanations of security descriptors in words.
+
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::values::structs::security::{
+    AccessMask, Ace, AceData, AceFlags, Acl, MandatoryMask, SecurityDescriptor,
+    SecurityDescriptorControl, Sid, SidNames,
+};
+
+
+/// The access rights that make up full control of a directory object.
+const FULL_CONTROL: AccessMask = AccessMask::from_bits_retain(0x000F_01FF);
+
+
+/// The kind of object that a GUID in an object ACE refers to.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum GuidKind {
+    /// An attribute, identified by the `schemaIDGUID` of its `attributeSchema` object.
+    Attribute,
+
+    /// A class, identified by the `schemaIDGUID` of its `classSchema` object.
+    Class,
+
+    /// A property set, identified by the `rightsGuid` of its `controlAccessRight` object.
+    PropertySet,
+
+    /// An extended right, identified by the `rightsGuid` of its `controlAccessRight` object.
+    ExtendedRight,
+
+    /// A validated write, identified by the `rightsGuid` of its `controlAccessRight` object.
+    ValidatedWrite,
+}
+impl GuidKind {
+    /// Derives the kind of a `controlAccessRight` object from its `validAccesses` attribute.
+    pub fn from_valid_accesses(valid_accesses: u32) -> Option<Self> {
+        let mask = AccessMask::from_bits_retain(valid_accesses);
+        if mask.contains(AccessMask::DsControlAccess) {
+            Some(Self::ExtendedRight)
+        } else if mask.contains(AccessMask::DsSelfWrite) {
+            Some(Self::ValidatedWrite)
+        } else if mask.intersects(AccessMask::DsReadProp | AccessMask::DsWriteProp) {
+            Some(Self::PropertySet)
+        } else {
+            None
+        }
+    }
+}
+
+
+/// The name of the object that a GUID in an object ACE refers to.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct GuidName {
+    pub kind: GuidKind,
+
+    /// The `lDAPDisplayName` of a schema object or the `displayName` of an extended right.
+    pub name: String,
+}
+
+
+/// The names of the schema objects and extended rights, by GUID.
+pub type GuidNames = BTreeMap<Uuid, GuidName>;
+
+
+fn quoted_guid_name(guid: &Uuid, guid_names: &GuidNames) -> String {
+    match guid_names.get(guid) {
+        Some(gn) => format!("'{}'", gn.name),
+        None => format!("{{{}}}", guid),
+    }
+}
+
+
+fn class_name(guid: &Uuid, guid_names: &GuidNames) -> String {
+    match guid_names.get(guid) {
+        Some(gn) => gn.name.clone(),
+        None => format!("{{{}}}", guid),
+    }
+}
+
+
+fn trustee_name(sid: &Sid, sid_names: &SidNames) -> String {
+    match sid.well_known_account_name() {
+        Some(name) => name.to_owned(),
+        None => sid.to_string_with_name(sid_names),
+    }
+}
+
+
+/// Describes the rights of an access mask, narrowed down by the object type of the ACE if given.
+fn explain_access_mask(mask: AccessMask, object_type: Option<&Uuid>, guid_names: &GuidNames) -> Vec<String> {
+    let mut rights = Vec::new();
+    let mut remaining = mask;
+    if object_type.is_none() && remaining.contains(FULL_CONTROL) {
+        rights.push("Full control".to_owned());
+        remaining.remove(FULL_CONTROL);
+    }
+
+    let property_kind = |verb: &str, ot: &Uuid| {
+        let kind = match guid_names.get(ot).map(|gn| gn.kind) {
+            Some(GuidKind::PropertySet) => "property set",
+            _ => "property",
+        };
+        format!("{} {} {}", verb, kind, quoted_guid_name(ot, guid_names))
+    };
+    let descriptions: [(AccessMask, String); 17] = [
+        (AccessMask::GenericAll, "Full control".to_owned()),
+        (AccessMask::GenericRead, "Read".to_owned()),
+        (AccessMask::GenericWrite, "Write".to_owned()),
+        (AccessMask::GenericExecute, "Execute".to_owned()),
+        (AccessMask::DsCreateChild, match object_type {
+            Some(ot) => format!("Create {} objects", class_name(ot, guid_names)),
+            None => "Create all child objects".to_owned(),
+        }),
+        (AccessMask::DsDeleteChild, match object_type {
+            Some(ot) => format!("Delete {} objects", class_name(ot, guid_names)),
+            None => "Delete all child objects".to_owned(),
+        }),
+        (AccessMask::DsListChildren, "List contents".to_owned()),
+        (AccessMask::DsSelfWrite, match object_type {
+            Some(ot) => format!("Validated write {}", quoted_guid_name(ot, guid_names)),
+            None => "All validated writes".to_owned(),
+        }),
+        (AccessMask::DsReadProp, match object_type {
+            Some(ot) => property_kind("Read", ot),
+            None => "Read all properties".to_owned(),
+        }),
+        (AccessMask::DsWriteProp, match object_type {
+            Some(ot) => property_kind("Write", ot),
+            None => "Write all properties".to_owned(),
+        }),
+        (AccessMask::DsDeleteTree, "Delete subtree".to_owned()),
+        (AccessMask::DsListObject, "List object".to_owned()),
+        (AccessMask::DsControlAccess, match object_type {
+            Some(ot) => format!("Extended right {}", quoted_guid_name(ot, guid_names)),
+            None => "All extended rights".to_owned(),
+        }),
+        (AccessMask::Delete, "Delete".to_owned()),
+        (AccessMask::ReadControl, "Read permissions".to_owned()),
+        (AccessMask::WriteDacl, "Modify permissions".to_owned()),
+        (AccessMask::WriteOwner, "Modify owner".to_owned()),
+    ];
+    for (flag, description) in descriptions {
+        if remaining.contains(flag) {
+            rights.push(description);
+            remaining.remove(flag);
+        }
+    }
+
+    if !remaining.is_empty() {
+        rights.push(format!("0x{:08X}", remaining.bits()));
+    }
+    rights
+}
+
+
+fn explain_mandatory_mask(mask: MandatoryMask) -> Vec<String> {
+    let mut rights = Vec::new();
+    if mask.contains(MandatoryMask::NoReadUp) {
+        rights.push("No read up".to_owned());
+    }
+    if mask.contains(MandatoryMask::NoWriteUp) {
+        rights.push("No write up".to_owned());
+    }
+    if mask.contains(MandatoryMask::NoExecuteUp) {
+        rights.push("No execute up".to_owned());
+    }
+    let remaining = mask - MandatoryMask::all();
+    if !remaining.is_empty() {
+        rights.push(format!("0x{:08X}", remaining.bits()));
+    }
+    rights
+}
+
+
+/// Describes the objects to which an ACE applies.
+fn explain_inheritance(flags: AceFlags, inherited_object_type: Option<&Uuid>, guid_names: &GuidNames) -> String {
+    let descendants = if flags.contains(AceFlags::NoPropagateInherit) { "child" } else { "descendant" };
+    let mut ret = if flags.contains(AceFlags::ContainerInherit) {
+        let objects = match inherited_object_type {
+            Some(iot) => format!("{} {} objects", descendants, class_name(iot, guid_names)),
+            None => format!("{} objects", descendants),
+        };
+        if flags.contains(AceFlags::InheritOnly) {
+            format!(" on {}", objects)
+        } else {
+            format!(" on this object and {}", objects)
+        }
+    } else {
+        String::new()
+    };
+    if flags.contains(AceFlags::Inherited) {
+        ret.push_str(" (inherited)");
+    }
+    ret
+}
+
+
+impl Ace {
+    /// Explains the ACE in words, e.g. `Allow BUILTIN\Administrators: Write property 'member' on
+    /// descendant group objects`.
+    ///
+    /// Object types are looked up in `guid_names`; unknown GUIDs are output in braces. Trustees are
+    /// output along with their account names in `sid_names`, if any.
+    pub fn explain(&self, guid_names: &GuidNames, sid_names: &SidNames) -> String {
+        let audit = match (self.flags.contains(AceFlags::SuccessfulAccessFlag), self.flags.contains(AceFlags::FailedAccessFlag)) {
+            (true, true) => "Audit success and failure by",
+            (true, false) => "Audit success by",
+            (false, true) => "Audit failure by",
+            (false, false) => "Audit",
+        };
+        let verb = match &self.data {
+            AceData::AccessAllowed { .. } | AceData::AccessAllowedObject { .. } => "Allow",
+            AceData::AccessDenied { .. } | AceData::AccessDeniedObject { .. } => "Deny",
+            AceData::SystemAudit { .. } | AceData::SystemAuditObject { .. } => audit,
+            AceData::AccessAllowedCallback { .. } | AceData::AccessAllowedCallbackObject { .. } => "Conditionally allow",
+            AceData::AccessDeniedCallback { .. } | AceData::AccessDeniedCallbackObject { .. } => "Conditionally deny",
+            AceData::SystemAuditCallback { .. } | AceData::SystemAuditCallbackObject { .. } => "Conditionally audit",
+            AceData::SystemMandatoryLabel { .. } => "Label",
+            AceData::SystemResourceAttribute { .. } => "Resource attribute for",
+            AceData::SystemScopedPolicyId { .. } => "Central access policy",
+            AceData::Other { kind } => return format!("Unknown ACE type 0x{:02X}", kind),
+        };
+
+        let rights = match &self.data {
+            AceData::SystemMandatoryLabel { mask, .. } => explain_mandatory_mask(*mask),
+            AceData::SystemResourceAttribute { attribute_data, .. } => vec![format!("'{}'", attribute_data.name)],
+            AceData::SystemScopedPolicyId { .. } => Vec::new(),
+            other => explain_access_mask(
+                other.access_mask().unwrap_or(AccessMask::empty()),
+                other.object_guid().as_ref(),
+                guid_names,
+            ),
+        };
+
+        let mut ret = verb.to_owned();
+        if let Some(sid) = self.data.sid() {
+            ret.push(' ');
+            ret.push_str(&trustee_name(sid, sid_names));
+        }
+        if !rights.is_empty() {
+            ret.push_str(": ");
+            ret.push_str(&rights.join(", "));
+        }
+        ret.push_str(&explain_inheritance(self.flags, self.data.inherit_object_guid().as_ref(), guid_names));
+        ret
+    }
+}
+
+
+fn explain_acl(
+    name: &str,
+    acl: Option<&Acl>,
+    protected: bool,
+    guid_names: &GuidNames,
+    sid_names: &SidNames,
+    lines: &mut Vec<String>,
+) {
+    let Some(acl) = acl else {
+        lines.push(format!("{}: none", name));
+        return;
+    };
+    if protected {
+        lines.push(format!("{} (protected from inheritance):", name));
+    } else {
+        lines.push(format!("{}:", name));
+    }
+    for ace in &acl.entries {
+        lines.push(format!("  {}", ace.explain(guid_names, sid_names)));
+    }
+}
+
+
+impl SecurityDescriptor {
+    /// Explains the security descriptor in words, one line for each of the owner, the group, each
+    /// ACL and each of its ACEs.
+    pub fn explain(&self, guid_names: &GuidNames, sid_names: &SidNames) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(owner) = self.owner.as_ref() {
+            lines.push(format!("Owner: {}", trustee_name(owner, sid_names)));
+        }
+        if let Some(group) = self.group.as_ref() {
+            lines.push(format!("Group: {}", trustee_name(group, sid_names)));
+        }
+        if self.dacl.is_some() || self.control.contains(SecurityDescriptorControl::DaclPresent) {
+            explain_acl(
+                "DACL",
+                self.dacl.as_ref(),
+                self.control.contains(SecurityDescriptorControl::DaclProtected),
+                guid_names,
+                sid_names,
+                &mut lines,
+            );
+        }
+        if self.sacl.is_some() || self.control.contains(SecurityDescriptorControl::SaclPresent) {
+            explain_acl(
+                "SACL",
+                self.sacl.as_ref(),
+                self.control.contains(SecurityDescriptorControl::SaclProtected),
+                guid_names,
+                sid_names,
+                &mut lines,
+            );
+        }
+        lines
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{GuidKind, GuidName, GuidNames};
+    use crate::values::structs::security::{SecurityDescriptor, Sid, SidNames};
+
+    #[test]
+    fn test_explain() {
+        let mut guid_names = GuidNames::new();
+        guid_names.insert(
+            "bf9679c0-0de6-11d0-a285-00aa003049e2".parse().unwrap(),
+            GuidName { kind: GuidKind::Attribute, name: "member".to_owned() },
+        );
+        guid_names.insert(
+            "bf967a9c-0de6-11d0-a285-00aa003049e2".parse().unwrap(),
+            GuidName { kind: GuidKind::Class, name: "group".to_owned() },
+        );
+        guid_names.insert(
+            "00299570-246d-11d0-a768-00aa006e0529".parse().unwrap(),
+            GuidName { kind: GuidKind::ExtendedRight, name: "Reset Password".to_owned() },
+        );
+        let mut sid_names = SidNames::new();
+        sid_names.insert(Sid::from_sddl("S-1-5-21-1-2-3-1104").unwrap(), "EXAMPLE\\jdoe".to_owned());
+
+        let sd = SecurityDescriptor::from_sddl(concat!(
+            "O:BAG:SYD:P",
+            "(A;;CCDCLCSWRPWPDTLOCRSDRCWDWO;;;SY)",
+            "(OA;CIIO;WP;bf9679c0-0de6-11d0-a285-00aa003049e2;bf967a9c-0de6-11d0-a285-00aa003049e2;BA)",
+            "(OD;CIID;CR;00299570-246d-11d0-a768-00aa006e0529;;WD)",
+            "(OA;;RP;4c164200-20c0-11d0-a768-00aa006e0529;;S-1-5-21-1-2-3-1104)",
+            "S:(AU;SAFA;WDWO;;;WD)",
+        )).unwrap();
+        assert_eq!(
+            sd.explain(&guid_names, &sid_names),
+            [
+                "Owner: BUILTIN\\Administrators",
+                "Group: NT AUTHORITY\\SYSTEM",
+                "DACL (protected from inheritance):",
+                "  Allow NT AUTHORITY\\SYSTEM: Full control",
+                "  Allow BUILTIN\\Administrators: Write property 'member' on descendant group objects",
+                "  Deny Everyone: Extended right 'Reset Password' on this object and descendant objects (inherited)",
+                "  Allow S-1-5-21-1-2-3-1104 (EXAMPLE\\jdoe): Read property {4c164200-20c0-11d0-a768-00aa006e0529}",
+                "SACL:",
+                "  Audit success and failure by Everyone: Modify permissions, Modify owner",
+            ],
+        );
+    }
+}
@@ -32,6 +32,16 @@ impl TrustForestTrustInfo {
             records,
         })
     }
+
+    /// The SIDs of the domains described by the records.
+    pub fn sids(&self) -> Vec<&Sid> {
+        self.records.iter()
+            .filter_map(|record| match record {
+                TrustInfoRecord::DomainInfo(domain_info) => Some(&domain_info.sid),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/66387402-cb2b-490c-bf2a-f4ad687397e4
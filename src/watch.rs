@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use chrono::Local;
+use ldap3::{Ldap, SearchEntry};
+
+use crate::{DEFAULT_FILTER, find_base_dn};
+use crate::bind::Authentication;
+use crate::connect::connect_with;
+use crate::controls::notification_control;
+use crate::entry::{entry_values, retrieve_ranged_values};
+use crate::error::Error;
+use crate::opts::{LdapScope, WatchOpts};
+use crate::output::EntryWriter;
+
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+
+/// Outputs the watched entries whenever they are modified, until interrupted.
+pub(crate) async fn watch(o: WatchOpts) -> Result<(), Error> {
+    // obtain the password once so that we can reconnect without asking again
+    let authentication = Authentication::from_opts(&o.connection)?;
+    let mut ldap = connect_with(&o.connection, &authentication).await?;
+
+    let base_dn = match o.base_dn.as_ref() {
+        Some(bdn) => bdn.clone(),
+        None => find_base_dn(&mut ldap).await?,
+    };
+
+    let mut writer = EntryWriter::new(&o.output);
+    if o.resolve_sids {
+        writer.enable_sid_names();
+    }
+    writer.begin();
+    let result = tokio::select! {
+        error = watch_with_reconnect(&o, &authentication, ldap, &base_dn, &mut writer) => Err(error),
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    writer.end();
+    result
+}
+
+
+/// Watches the entries, reconnecting whenever the connection is lost, until an error occurs from
+/// which reconnecting does not help.
+async fn watch_with_reconnect(
+    o: &WatchOpts,
+    authentication: &Authentication,
+    mut ldap: Ldap,
+    base_dn: &str,
+    writer: &mut EntryWriter,
+) -> Error {
+    loop {
+        match watch_changes(&mut ldap, base_dn, o.scope, &o.attributes, writer).await {
+            Ok(()) => eprintln!("adldapsearch: the server has ended the notification search; reconnecting"),
+            Err(e) if e.is_server_unavailable() => eprintln!("adldapsearch: {}; reconnecting", e),
+            Err(e) => return e,
+        }
+
+        let mut delay = MIN_RECONNECT_DELAY;
+        ldap = loop {
+            tokio::time::sleep(delay).await;
+            match connect_with(&o.connection, authentication).await {
+                Ok(l) => break l,
+                Err(e) if e.is_server_unavailable() => {
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    eprintln!("adldapsearch: {}; retrying in {} s", e, delay.as_secs());
+                },
+                Err(e) => return e,
+            }
+        };
+    }
+}
+
+
+/// Runs a notification search and outputs the entries returned by it until the server ends it.
+async fn watch_changes(
+    ldap: &mut Ldap,
+    base_dn: &str,
+    scope: LdapScope,
+    attributes: &[String],
+    writer: &mut EntryWriter,
+) -> Result<(), Error> {
+    // Active Directory only accepts this filter for notification searches
+    ldap.with_controls(notification_control());
+    let mut search_stream = ldap.streaming_search(base_dn, scope.into(), DEFAULT_FILTER, attributes)
+        .await.map_err(Error::ldap("notification search"))?;
+    while let Some(result_entry) = search_stream.next().await.map_err(Error::ldap("notification search"))? {
+        if result_entry.is_ref() {
+            continue;
+        }
+        let received = Local::now();
+        let entry = SearchEntry::construct(result_entry);
+        let dn = entry.dn.clone();
+        let mut all_keys_values = entry_values(entry);
+        retrieve_ranged_values(ldap, &dn, &[], &mut all_keys_values).await?;
+        if let Some(sid_cache) = writer.sid_cache_mut() {
+            sid_cache.resolve(ldap, &all_keys_values).await?;
+        }
+        writer.write_received_entry(&dn, &all_keys_values, received)?;
+    }
+
+    search_stream.finish().await
+        .success().map_err(Error::ldap("notification search"))?;
+    Ok(())
+}